@@ -1,5 +1,5 @@
 use std::fs;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{Cursor, Read};
 
 use clap::Parser;
 use serde::Serialize;
@@ -13,10 +13,6 @@ struct Args {
     #[arg(index = 1)]
     filename: String,
 
-    /// Skip first line of input
-    #[arg(short, long)]
-    skip_first_line: bool,
-
     /// Decode only the handler sections
     #[arg(short, long)]
     only_header: bool,
@@ -31,7 +27,7 @@ type Sequence = indexmap::IndexMap<String, Value>;
 enum Value {
     Missing(()),
     Float(f64),
-    Integer(i32),
+    Integer(i64),
     String(String),
     Replication(Vec<Sequence>),
     Sequence(Sequence),
@@ -57,18 +53,22 @@ fn main() -> Result<(), Error> {
         tables.table_d.insert(seq.xy, seq);
     }
 
+    // Dump every message in the file, skipping data around them (e.g. GTS headings)
     let file = fs::File::open(args.filename)?;
-    let mut reader = BufReader::new(file);
-    if args.skip_first_line {
-        // Some files have a first line that is not part of the BUFR message
-        let mut buf = String::new();
-        reader.read_line(&mut buf)?;
+    for message in MessageScanner::new(file) {
+        dump_message(&message?.data, &tables, args.only_header)?;
     }
 
+    Ok(())
+}
+
+fn dump_message(data: &[u8], tables: &Tables, only_header: bool) -> Result<(), Error> {
+    let mut reader = Cursor::new(data);
+
     // Parse header sections
     let header = HeaderSections::read(&mut reader)?;
 
-    if args.only_header {
+    if only_header {
         let Ok(json) = serde_json::to_string_pretty(&header) else {
             return Err(Error::Fatal("Failed to serialize to JSON".to_string()));
         };
@@ -77,7 +77,7 @@ fn main() -> Result<(), Error> {
     }
 
     // Parse data section
-    let data_spec = DataSpec::from_data_description(&header.data_description_section, &tables)?;
+    let data_spec = DataSpec::from_data_description(&header.data_description_section, tables)?;
 
     let mut data_reader = DataReader::new(&mut reader, &data_spec)?;
     let mut subsets = Subsets::new();
@@ -86,11 +86,11 @@ fn main() -> Result<(), Error> {
     loop {
         match data_reader.read_event()? {
             DataEvent::SubsetStart(_) => {
-                let subset = parse_sequence(&mut data_reader, &tables)?;
+                let subset = parse_sequence(&mut data_reader, tables)?;
                 subsets.push(subset);
             }
             DataEvent::CompressedStart => {
-                compressed = Some(parse_sequence(&mut data_reader, &tables)?);
+                compressed = Some(parse_sequence(&mut data_reader, tables)?);
             }
             DataEvent::Eof => {
                 break;
@@ -121,6 +121,28 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+fn make_label(tables: &Tables, idx: u16, xy: XY, unit: &str) -> Result<String, Error> {
+    let Some(b) = tables.table_b.get(&xy) else {
+        return Err(Error::Fatal(format!("Unknown data descriptor: {:#?}", xy)));
+    };
+    Ok(match unit {
+        "Numeric" => format!("{}: {}", idx, b.element_name),
+        _ => format!("{}: {} ({})", idx, b.element_name, unit),
+    })
+}
+
+fn convert_value(value: tinybufr::Value) -> Value {
+    match value {
+        tinybufr::Value::Missing => Value::Missing(()),
+        tinybufr::Value::String(v) => Value::String(v),
+        tinybufr::Value::Decimal(_, s) if s < 0 => Value::Float(value.as_f64().unwrap()),
+        v => match v.as_i64() {
+            Some(v) => Value::Integer(v),
+            None => Value::Float(v.as_f64().unwrap()),
+        },
+    }
+}
+
 fn parse_sequence<R: Read>(
     data_reader: &mut DataReader<'_, R>,
     tables: &Tables,
@@ -130,52 +152,39 @@ fn parse_sequence<R: Read>(
     loop {
         match data_reader.read_event()? {
             DataEvent::SubsetEnd | DataEvent::SequenceEnd | DataEvent::ReplicationItemEnd => break,
-            DataEvent::Data { idx, value, xy } => {
-                let Some(b) = tables.table_b.get(&xy) else {
-                    return Err(Error::Fatal(format!("Unknown data descriptor: {:#?}", xy)));
-                };
-                let label = match b.unit {
-                    "Numeric" => format!("{}: {}", idx, b.element_name),
-                    _ => format!("{}: {} ({})", idx, b.element_name, b.unit),
-                };
-                let value = match value {
-                    tinybufr::Value::Missing => Value::Missing(()),
-                    tinybufr::Value::Decimal(v, s) => {
-                        if s >= 0 {
-                            Value::Integer(v * 10f64.powi(s as i32) as i32)
-                        } else {
-                            Value::Float(v as f64 * 10f64.powi(s as i32))
-                        }
-                    }
-                    tinybufr::Value::Integer(v) => Value::Integer(v),
-                    tinybufr::Value::String(v) => Value::String(v.clone()),
-                };
-                subset.insert(label, value);
+            DataEvent::Data {
+                idx,
+                xy,
+                unit,
+                value,
+                ..
+            } => {
+                let label = make_label(tables, idx, xy, unit)?;
+                subset.insert(label, convert_value(value));
             }
-            DataEvent::CompressedData { idx, xy, values } => {
-                let Some(b) = tables.table_b.get(&xy) else {
-                    return Err(Error::Fatal(format!("Unknown data descriptor: {:#?}", xy)));
-                };
-                let label = match b.unit {
-                    "Numeric" => format!("{}: {}", idx, b.element_name),
-                    _ => format!("{}: {} ({})", idx, b.element_name, b.unit),
-                };
+            DataEvent::CompressedData {
+                idx,
+                xy,
+                unit,
+                values,
+                ..
+            } => {
+                let label = make_label(tables, idx, xy, unit)?;
+                let vals: Vec<Value> = values.into_iter().map(convert_value).collect();
+                subset.insert(label, Value::CompressedData(vals));
+            }
+            DataEvent::Characters { idx, value } => {
+                subset.insert(format!("{}", idx), Value::String(value));
+            }
+            DataEvent::CompressedCharacters { idx, values } => {
                 let vals: Vec<Value> = values
                     .into_iter()
                     .map(|v| match v {
-                        tinybufr::Value::Missing => Value::Missing(()),
-                        tinybufr::Value::Decimal(v, s) => {
-                            if s >= 0 {
-                                Value::Integer(v * 10f64.powi(s as i32) as i32)
-                            } else {
-                                Value::Float(v as f64 * 10f64.powi(s as i32))
-                            }
-                        }
-                        tinybufr::Value::Integer(v) => Value::Integer(v),
-                        tinybufr::Value::String(v) => Value::String(v.clone()),
+                        tinybufr::Value::String(v) => Value::String(v),
+                        _ => Value::Missing(()),
                     })
                     .collect();
-                subset.insert(label, Value::CompressedData(vals));
+                subset.insert(format!("{}", idx), Value::CompressedData(vals));
             }
             DataEvent::SequenceStart { idx, xy } => {
                 let Some(d) = tables.table_d.get(&xy) else {
@@ -193,7 +202,15 @@ fn parse_sequence<R: Read>(
                 let replication = parse_replication(data_reader, tables)?;
                 subset.insert(label, Value::Replication(replication));
             }
-            DataEvent::OperatorHandled { .. } => {}
+            DataEvent::OperatorHandled { .. }
+            | DataEvent::ReferenceValueDefined { .. }
+            | DataEvent::AssociatedField { .. }
+            | DataEvent::CompressedAssociatedField { .. }
+            | DataEvent::SkippedData { .. }
+            | DataEvent::BackReference { .. }
+            | DataEvent::ElementLayout { .. }
+            | DataEvent::NotPresent { .. }
+            | DataEvent::Custom { .. } => {}
             DataEvent::Eof => {
                 break;
             }
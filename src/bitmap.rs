@@ -0,0 +1,270 @@
+//! Data present bitmaps and backward references (operators 2-22 to 2-37)
+
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::{Error, XY};
+
+/// Kind of values which refer back to previous data elements through a data present bitmap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum BackReferenceKind {
+    /// Quality information (2-22-000)
+    QualityInformation,
+    /// Substituted values (2-23-255)
+    SubstitutedValue,
+    /// First-order statistical values (2-24-255)
+    FirstOrderStatistics,
+    /// Difference statistical values (2-25-255)
+    DifferenceStatistics,
+    /// Replaced/retained values (2-32-255)
+    ReplacedValue,
+    /// Event (2-41-000)
+    Event,
+    /// Conditioning event (2-42-000)
+    ConditioningEvent,
+    /// Categorical forecast values (2-43-000)
+    CategoricalForecast,
+}
+
+impl BackReferenceKind {
+    pub(crate) fn from_operator(x: u8) -> Option<Self> {
+        Some(match x {
+            22 => Self::QualityInformation,
+            23 => Self::SubstitutedValue,
+            24 => Self::FirstOrderStatistics,
+            25 => Self::DifferenceStatistics,
+            32 => Self::ReplacedValue,
+            41 => Self::Event,
+            42 => Self::ConditioningEvent,
+            43 => Self::CategoricalForecast,
+            _ => return None,
+        })
+    }
+
+    /// Whether a data element following the bitmap refers back to the next referenced element.
+    /// The other kinds refer back only through their 2-XX-255 markers.
+    pub(crate) fn applies_to_element(&self, xy: XY) -> bool {
+        match self {
+            Self::QualityInformation => xy.x == 33,
+            Self::Event | Self::ConditioningEvent | Self::CategoricalForecast => xy.x != 31,
+            _ => false,
+        }
+    }
+}
+
+/// The backward reference list of a subset and the bitmaps applied to it
+pub(crate) struct BackReferences<E> {
+    /// Data elements of the subset in the order they were decoded
+    elements: Vec<E>,
+    /// Elements before this position were cancelled by 2-35-000
+    first: usize,
+    /// Position of the element referred to by the first bit of bitmaps.
+    /// It is fixed by the first bitmap until cancelled by 2-35-000.
+    bitmap_start: Option<usize>,
+    /// The operator waiting for (or using) a bitmap, and the number of elements preceding it
+    operator: Option<(BackReferenceKind, usize)>,
+    /// Data present indicators (0-31-031) collected so far
+    bitmap: Option<Vec<bool>>,
+    /// Whether the bitmap being collected is defined for reuse (2-36-000)
+    define_for_reuse: bool,
+    reusable: Option<Vec<usize>>,
+    /// Positions of the elements not yet referred to by the current operator
+    targets: VecDeque<usize>,
+}
+
+impl<E> Default for BackReferences<E> {
+    fn default() -> Self {
+        Self {
+            elements: Vec::new(),
+            first: 0,
+            bitmap_start: None,
+            operator: None,
+            bitmap: None,
+            define_for_reuse: false,
+            reusable: None,
+            targets: VecDeque::new(),
+        }
+    }
+}
+
+impl<E> BackReferences<E> {
+    /// Append a decoded data element to the backward reference list
+    pub fn push(&mut self, element: E) {
+        self.elements.push(element);
+    }
+
+    /// Number of elements pushed so far
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Append the elements from `start` another `times` times (e.g. for delayed repetition)
+    pub fn repeat(&mut self, start: usize, times: usize)
+    where
+        E: Clone,
+    {
+        let end = self.elements.len();
+        for _ in 0..times {
+            self.elements.extend_from_within(start..end);
+        }
+    }
+
+    /// Start an operator which refers back to data elements (e.g. 2-22-000)
+    pub fn begin(&mut self, kind: BackReferenceKind) {
+        self.operator = Some((kind, self.elements.len()));
+        self.bitmap = Some(Vec::new());
+        self.targets.clear();
+    }
+
+    /// Conclude the operator (e.g. 2-41-255)
+    pub fn end(&mut self, kind: BackReferenceKind) {
+        if self.kind() == Some(kind) {
+            self.operator = None;
+            self.bitmap = None;
+            self.targets.clear();
+        }
+    }
+
+    /// Define the bitmap which follows for reuse (2-36-000)
+    pub fn define_bitmap(&mut self) {
+        self.define_for_reuse = true;
+        if self.bitmap.is_none() {
+            self.bitmap = Some(Vec::new());
+        }
+    }
+
+    /// Apply the previously defined bitmap to the current operator (2-37-000)
+    pub fn reuse_bitmap(&mut self) -> Result<(), Error> {
+        let Some(reusable) = &self.reusable else {
+            return Err(Error::Fatal(
+                "No bitmap has been defined for reuse".to_string(),
+            ));
+        };
+        self.bitmap = None;
+        self.targets = reusable.iter().copied().collect();
+        Ok(())
+    }
+
+    /// Cancel the reuse of the defined bitmap (2-37-255)
+    pub fn cancel_reuse(&mut self) {
+        self.reusable = None;
+    }
+
+    /// Cancel the backward reference (2-35-000)
+    pub fn cancel(&mut self) {
+        *self = Self {
+            first: self.elements.len(),
+            elements: std::mem::take(&mut self.elements),
+            ..Default::default()
+        };
+    }
+
+    /// Whether data present indicators are being collected
+    pub fn is_collecting(&self) -> bool {
+        self.bitmap.is_some()
+    }
+
+    /// Append a data present indicator (0-31-031) to the bitmap being collected
+    pub fn push_bit(&mut self, present: bool) {
+        if let Some(bitmap) = &mut self.bitmap {
+            bitmap.push(present);
+        }
+    }
+
+    /// Complete the bitmap being collected, if any indicators have been collected
+    pub fn finish_bitmap(&mut self) -> Result<(), Error> {
+        let Some(bitmap) = self.bitmap.take_if(|bitmap| !bitmap.is_empty()) else {
+            return Ok(());
+        };
+        let preceding = match self.operator {
+            Some((_, preceding)) => preceding,
+            None => self.elements.len().saturating_sub(bitmap.len()),
+        };
+        let start = match self.bitmap_start {
+            Some(start) => start,
+            None if preceding >= self.first + bitmap.len() => preceding - bitmap.len(),
+            None => {
+                return Err(Error::Fatal(format!(
+                    "Bitmap of length {} refers to more than {} preceding data elements",
+                    bitmap.len(),
+                    preceding - self.first
+                )));
+            }
+        };
+        if start + bitmap.len() > self.elements.len() {
+            return Err(Error::Fatal(format!(
+                "Bitmap of length {} exceeds the backward reference list",
+                bitmap.len()
+            )));
+        }
+        self.bitmap_start = Some(start);
+        let targets: Vec<usize> = bitmap
+            .iter()
+            .enumerate()
+            .filter(|(_, present)| **present)
+            .map(|(i, _)| start + i)
+            .collect();
+        if std::mem::take(&mut self.define_for_reuse) {
+            self.reusable = Some(targets.clone());
+        }
+        self.targets = targets.into();
+        Ok(())
+    }
+
+    /// The kind of the current operator
+    pub fn kind(&self) -> Option<BackReferenceKind> {
+        self.operator.map(|(kind, _)| kind)
+    }
+
+    /// Take the next element referred to by the current operator
+    pub fn next_target(&mut self) -> Option<(usize, &E)> {
+        let position = self.targets.pop_front()?;
+        Some((position, &self.elements[position]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitmap_targets() {
+        let mut refs = BackReferences::default();
+        for i in 0..4 {
+            refs.push(i);
+        }
+        refs.begin(BackReferenceKind::QualityInformation);
+        refs.define_bitmap();
+        // The bitmap refers to the last three elements preceding the operator
+        for present in [true, false, true] {
+            refs.push(99);
+            refs.push_bit(present);
+        }
+        refs.finish_bitmap().unwrap();
+        assert!(!refs.is_collecting());
+        assert_eq!(refs.next_target(), Some((1, &1)));
+        assert_eq!(refs.next_target(), Some((3, &3)));
+        assert_eq!(refs.next_target(), None);
+
+        // Reuse the defined bitmap for another operator
+        refs.begin(BackReferenceKind::SubstitutedValue);
+        refs.reuse_bitmap().unwrap();
+        assert_eq!(refs.kind(), Some(BackReferenceKind::SubstitutedValue));
+        assert_eq!(refs.next_target(), Some((1, &1)));
+
+        // A new bitmap keeps the start of the first bitmap
+        refs.begin(BackReferenceKind::ReplacedValue);
+        refs.push_bit(false);
+        refs.push_bit(true);
+        refs.finish_bitmap().unwrap();
+        assert_eq!(refs.next_target(), Some((2, &2)));
+
+        // After cancelling, bitmaps may only refer to newer elements
+        refs.cancel();
+        refs.begin(BackReferenceKind::QualityInformation);
+        refs.push_bit(true);
+        assert!(refs.finish_bitmap().is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! GTS bulletins (WMO-No. 386) carrying BUFR messages
+
+use serde::Serialize;
+
+use crate::{Error, FramedMessage, MessageScanner};
+
+const SOH: u8 = 0x01;
+const ETX: u8 = 0x03;
+
+/// Abbreviated heading of a bulletin: `TTAAii CCCC YYGGgg [BBB]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AbbreviatedHeading {
+    /// Data type and designators (e.g. "ISTC62")
+    pub ttaaii: String,
+    /// Location indicator of the originating station (e.g. "RJTD")
+    pub cccc: String,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub bbb: Option<HeadingIndicator>,
+}
+
+/// BBB indicator of the abbreviated heading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum HeadingIndicator {
+    /// RRx: delayed bulletin
+    Delayed(char),
+    /// CCx: correction
+    Correction(char),
+    /// AAx: amendment
+    Amendment(char),
+    /// Pxx: segment of a bulletin
+    Segment(char, char),
+}
+
+impl AbbreviatedHeading {
+    /// Parse a heading line such as "ISTC62 RJTD 310000" or "IUKN01 RJTD 180000 CCA"
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let invalid = || Error::Fatal(format!("Invalid abbreviated heading {:?}", line));
+        let mut groups = line.split_ascii_whitespace();
+        let (Some(ttaaii), Some(cccc), Some(yygggg)) =
+            (groups.next(), groups.next(), groups.next())
+        else {
+            return Err(invalid());
+        };
+        let is_ttaaii = ttaaii.len() == 6
+            && ttaaii.as_bytes()[..4].iter().all(u8::is_ascii_alphabetic)
+            && ttaaii.as_bytes()[4..].iter().all(u8::is_ascii_digit);
+        let is_cccc = cccc.len() == 4 && cccc.bytes().all(|b| b.is_ascii_alphanumeric());
+        if !is_ttaaii
+            || !is_cccc
+            || yygggg.len() != 6
+            || !yygggg.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let bbb = match groups.next() {
+            None => None,
+            Some(bbb) => Some(HeadingIndicator::parse(bbb).ok_or_else(invalid)?),
+        };
+        if groups.next().is_some() {
+            return Err(invalid());
+        }
+        let number = |i: usize| yygggg[i..i + 2].parse::<u8>().map_err(|_| invalid());
+        Ok(Self {
+            ttaaii: ttaaii.to_string(),
+            cccc: cccc.to_string(),
+            day: number(0)?,
+            hour: number(2)?,
+            minute: number(4)?,
+            bbb,
+        })
+    }
+
+    /// T1T2: data type designators (e.g. "IS" for observational data in BUFR)
+    pub fn tt(&self) -> &str {
+        &self.ttaaii[..2]
+    }
+
+    pub fn is_correction(&self) -> bool {
+        matches!(self.bbb, Some(HeadingIndicator::Correction(_)))
+    }
+
+    pub fn is_amendment(&self) -> bool {
+        matches!(self.bbb, Some(HeadingIndicator::Amendment(_)))
+    }
+}
+
+impl HeadingIndicator {
+    fn parse(bbb: &str) -> Option<Self> {
+        let &[a, b, c] = bbb.as_bytes() else {
+            return None;
+        };
+        if !c.is_ascii_uppercase() {
+            return None;
+        }
+        Some(match (a, b) {
+            (b'R', b'R') => Self::Delayed(c as char),
+            (b'C', b'C') => Self::Correction(c as char),
+            (b'A', b'A') => Self::Amendment(c as char),
+            (b'P', b) if b.is_ascii_uppercase() => Self::Segment(b as char, c as char),
+            _ => return None,
+        })
+    }
+}
+
+/// A GTS bulletin with the BUFR messages in it
+#[derive(Debug, Clone)]
+pub struct Bulletin {
+    /// Transmission sequence number (nnn) following SOH
+    pub sequence_number: Option<u32>,
+    pub heading: AbbreviatedHeading,
+    /// BUFR messages, with offsets from the start of the bulletin (SOH or the heading)
+    pub messages: Vec<FramedMessage>,
+}
+
+impl Bulletin {
+    /// Parse a bulletin, which starts with SOH or directly with the abbreviated heading
+    ///
+    /// Use [`Bulletin::parse_all`] for data with more than one bulletin.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut pos = 0;
+        let bulletin = Self::parse_at(data, &mut pos)?;
+        if pos < data.len() {
+            return Err(Error::Fatal(format!(
+                "Unexpected data at offset {} after the end of the bulletin",
+                pos
+            )));
+        }
+        Ok(bulletin)
+    }
+
+    /// Parse every SOH...ETX envelope in the data (e.g. a GTS feed or a file in the WMO FTP
+    /// format, where each envelope follows an 8-digit length and a 2-digit format identifier)
+    ///
+    /// Data not starting with SOH (or the FTP prefix) is parsed as a single bulletin starting
+    /// with the abbreviated heading.
+    pub fn parse_all(data: &[u8]) -> Result<Vec<Self>, Error> {
+        let Some(mut pos) = envelope_start(data, 0) else {
+            return Ok(vec![Self::parse(data)?]);
+        };
+        let mut bulletins = vec![Self::parse_at(data, &mut pos)?];
+        loop {
+            pos = skip_line_breaks(data, pos);
+            if pos == data.len() {
+                return Ok(bulletins);
+            }
+            let Some(start) = envelope_start(data, pos) else {
+                return Err(Error::Fatal(format!(
+                    "Unexpected data at offset {} between bulletins",
+                    pos
+                )));
+            };
+            pos = start;
+            bulletins.push(Self::parse_at(data, &mut pos)?);
+        }
+    }
+
+    /// Parse the bulletin at `pos` and move `pos` past its end (ETX if any)
+    fn parse_at(data: &[u8], pos: &mut usize) -> Result<Self, Error> {
+        let start = *pos;
+        let mut sequence_number = None;
+        if data.get(*pos) == Some(&SOH) {
+            *pos = skip_line_breaks(data, *pos + 1);
+            let line = next_line(data, pos);
+            let Some(n) = std::str::from_utf8(line)
+                .ok()
+                .filter(|s| matches!(s.len(), 3 | 5))
+                .and_then(|s| s.parse().ok())
+            else {
+                return Err(Error::Fatal(format!(
+                    "Invalid transmission sequence number {:?}",
+                    String::from_utf8_lossy(line)
+                )));
+            };
+            sequence_number = Some(n);
+        }
+        let line = next_line(data, pos);
+        let Ok(line) = std::str::from_utf8(line) else {
+            return Err(Error::Fatal(
+                "Abbreviated heading is not ASCII text".to_string(),
+            ));
+        };
+        let heading = AbbreviatedHeading::parse(line)?;
+
+        // BUFR messages up to ETX, the next bulletin or the end of the data
+        let mut messages = vec![];
+        loop {
+            *pos = skip_line_breaks(data, *pos);
+            match data.get(*pos) {
+                None | Some(&SOH) => break,
+                Some(&ETX) => {
+                    *pos += 1;
+                    break;
+                }
+                Some(_) => {}
+            }
+            let Some(message) = MessageScanner::new(&data[*pos..]).next().transpose()? else {
+                break;
+            };
+            if message.offset != 0 {
+                return Err(Error::Fatal(format!(
+                    "Unexpected data at offset {} in the bulletin {}",
+                    *pos, heading.ttaaii
+                )));
+            }
+            let offset = (*pos - start) as u64;
+            *pos += message.data.len();
+            messages.push(FramedMessage { offset, ..message });
+        }
+        Ok(Self {
+            sequence_number,
+            heading,
+            messages,
+        })
+    }
+}
+
+/// Position of SOH at `pos`, or after the length and format identifier of the FTP format
+fn envelope_start(data: &[u8], pos: usize) -> Option<usize> {
+    const FTP_PREFIX_LENGTH: usize = 8 + 2;
+    let rest = &data[pos..];
+    if rest.first() == Some(&SOH) {
+        return Some(pos);
+    }
+    let is_ftp_prefix = rest.len() > FTP_PREFIX_LENGTH
+        && rest[..FTP_PREFIX_LENGTH].iter().all(u8::is_ascii_digit)
+        && rest[FTP_PREFIX_LENGTH] == SOH;
+    is_ftp_prefix.then_some(pos + FTP_PREFIX_LENGTH)
+}
+
+fn skip_line_breaks(data: &[u8], mut pos: usize) -> usize {
+    while pos < data.len() && matches!(data[pos], b'\r' | b'\n') {
+        pos += 1;
+    }
+    pos
+}
+
+/// Take the line at `pos` and move `pos` to the start of the next line
+fn next_line<'d>(data: &'d [u8], pos: &mut usize) -> &'d [u8] {
+    let start = *pos;
+    let end = data[start..]
+        .iter()
+        .position(|b| matches!(b, b'\r' | b'\n'))
+        .map_or(data.len(), |n| start + n);
+    *pos = skip_line_breaks(data, end);
+    &data[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading() {
+        let heading = AbbreviatedHeading::parse("ISTC62 RJTD 310000").unwrap();
+        assert_eq!(heading.ttaaii, "ISTC62");
+        assert_eq!(heading.tt(), "IS");
+        assert_eq!(heading.cccc, "RJTD");
+        assert_eq!((heading.day, heading.hour, heading.minute), (31, 0, 0));
+        assert_eq!(heading.bbb, None);
+
+        let heading = AbbreviatedHeading::parse("IUKN01 RJTD 180000 CCA").unwrap();
+        assert!(heading.is_correction());
+        assert!(!heading.is_amendment());
+        let heading = AbbreviatedHeading::parse("IUKN01 RJTD 180000 AAB").unwrap();
+        assert_eq!(heading.bbb, Some(HeadingIndicator::Amendment('B')));
+        let heading = AbbreviatedHeading::parse("IUKN01 RJTD 180000 PAA").unwrap();
+        assert_eq!(heading.bbb, Some(HeadingIndicator::Segment('A', 'A')));
+
+        assert!(AbbreviatedHeading::parse("ISTC62 RJTD").is_err());
+        assert!(AbbreviatedHeading::parse("ISTC6X RJTD 310000").is_err());
+        assert!(AbbreviatedHeading::parse("ISTC62 RJTD 3100").is_err());
+        assert!(AbbreviatedHeading::parse("ISTC62 RJTD 310000 XYZ").is_err());
+    }
+
+    #[test]
+    fn test_bulletin() {
+        let mut message = b"BUFR\x00\x00\x10\x04".to_vec();
+        message.extend([0; 4]);
+        message.extend(b"7777");
+
+        let mut data = b"\x01\r\r\n123\r\r\nIUSN01 RJTD 010600 RRA\r\r\n".to_vec();
+        let offset = data.len() as u64;
+        data.extend(&message);
+        data.extend(b"\r\r\n\x03");
+
+        let bulletin = Bulletin::parse(&data).unwrap();
+        assert_eq!(bulletin.sequence_number, Some(123));
+        assert_eq!(bulletin.heading.ttaaii, "IUSN01");
+        assert_eq!(bulletin.heading.bbb, Some(HeadingIndicator::Delayed('A')));
+        assert_eq!(bulletin.messages.len(), 1);
+        assert_eq!(bulletin.messages[0].offset, offset);
+        assert_eq!(bulletin.messages[0].data, message);
+
+        assert!(Bulletin::parse(b"\x01\r\r\n1X3\r\r\nIUSN01 RJTD 010600\r\r\n").is_err());
+        assert!(Bulletin::parse(b"IUSN01 RJTD 010600\r\r\njunk").is_err());
+    }
+
+    #[test]
+    fn test_parse_all() {
+        // Messages with SOH and ETX bytes inside
+        let mut first = b"BUFR\x00\x00\x10\x04".to_vec();
+        first.extend([1, 3, 1, 3]);
+        first.extend(b"7777");
+        let mut second = first.clone();
+        second[8..12].copy_from_slice(&[3, 3, 3, 3]);
+
+        // Two bulletins in the WMO FTP format: length, format identifier and the envelope
+        let envelope = |header: &[u8], messages: &[&[u8]]| {
+            let mut envelope = header.to_vec();
+            messages
+                .iter()
+                .for_each(|message| envelope.extend(*message));
+            envelope.extend(b"\r\r\n\x03");
+            let mut data = format!("{:08}00", envelope.len()).into_bytes();
+            data.extend(envelope);
+            data
+        };
+        let mut data = envelope(
+            b"\x01\r\r\n001\r\r\nIUSN01 RJTD 010600\r\r\n",
+            &[&first, &first],
+        );
+        let second_start = data.len();
+        let header = b"\x01\r\r\n002\r\r\nIUSN02 RJTD 010600 CCA\r\r\n";
+        let offset = header.len() as u64;
+        data.extend(envelope(header, &[&second]));
+
+        let bulletins = Bulletin::parse_all(&data).unwrap();
+        assert_eq!(bulletins.len(), 2);
+        assert_eq!(bulletins[0].sequence_number, Some(1));
+        assert_eq!(bulletins[0].heading.ttaaii, "IUSN01");
+        assert_eq!(bulletins[0].messages.len(), 2);
+        assert_eq!(bulletins[0].messages[1].data, first);
+        assert_eq!(bulletins[1].sequence_number, Some(2));
+        assert_eq!(bulletins[1].heading.ttaaii, "IUSN02");
+        assert!(bulletins[1].heading.is_correction());
+        assert_eq!(bulletins[1].messages.len(), 1);
+        assert_eq!(bulletins[1].messages[0].offset, offset);
+        assert_eq!(bulletins[1].messages[0].data, second);
+
+        // A single bulletin needs parse_all
+        assert!(Bulletin::parse(&data[10..]).is_err());
+
+        // Envelopes without the FTP prefix, and data between them
+        let mut data = data[second_start + 10..].to_vec();
+        assert_eq!(Bulletin::parse_all(&data).unwrap().len(), 1);
+        data.extend(b"junk");
+        assert!(Bulletin::parse_all(&data).is_err());
+    }
+}
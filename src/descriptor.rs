@@ -51,9 +51,16 @@ pub enum ResolvedDescriptor<'a> {
     Replication {
         y: u8,
         delayed_bits: u8,
+        /// Delayed descriptor replication factor (class 31) when YYY = 0
+        delayed_factor: Option<XY>,
         descriptors: Vec<ResolvedDescriptor<'a>>,
     },
     Operator(XY),
+    /// Local element descriptor following 2-06-YYY that is not found in Table B
+    LocalData {
+        xy: XY,
+        bits: u8,
+    },
     Sequence(&'a TableDEntry, Vec<ResolvedDescriptor<'a>>),
 }
 
@@ -100,24 +107,28 @@ pub(crate) fn resolve_descriptors<'a>(
     while pos < descriptors.len() {
         match &descriptors[pos] {
             &Descriptor { f: 1, x, y } => {
-                let delayed_bits = match y {
+                let (delayed_bits, delayed_factor) = match y {
                     // delayed replication when YYY = 0
                     0 => {
                         pos += 1;
-                        match descriptors[pos] {
+                        let delayed_bits = match descriptors[pos] {
                             Descriptor { f: 0, x: 31, y: 0 } => 1,
                             Descriptor { f: 0, x: 31, y: 1 } => 8,
                             Descriptor { f: 0, x: 31, y: 2 } => 16,
                             Descriptor { f: 0, x: 31, y: 3 } => 8, // Note: JMA-local?
+                            // Delayed repetition: the block is stored once and repeated
+                            Descriptor { f: 0, x: 31, y: 11 } => 8,
+                            Descriptor { f: 0, x: 31, y: 12 } => 16,
                             desc => {
                                 return Err(Error::Fatal(format!(
                                     "Unsupported delayed descriptor replication factor: {:#?}",
                                     desc
                                 )));
                             }
-                        }
+                        };
+                        (delayed_bits, Some(descriptors[pos].xy()))
                     }
-                    _ => 0,
+                    _ => (0, None),
                 };
                 pos += 1;
                 if pos + x as usize > descriptors.len() {
@@ -127,9 +138,23 @@ pub(crate) fn resolve_descriptors<'a>(
                     y,
                     descriptors: resolve_descriptors(tables, &descriptors[pos..pos + x as usize])?,
                     delayed_bits,
+                    delayed_factor,
                 });
                 pos += x as usize;
             }
+            &Descriptor { f: 2, x: 6, y }
+                if descriptors.get(pos + 1).is_some_and(|next| next.f == 0) =>
+            {
+                resolved.push(ResolvedDescriptor::Operator(XY { x: 6, y }));
+                // The following local descriptor is decoded normally only if it is known
+                // with the declared width; otherwise its YYY bits are skipped
+                let xy = descriptors[pos + 1].xy();
+                resolved.push(match tables.table_b.get(&xy) {
+                    Some(b) if b.bits == y as u16 => ResolvedDescriptor::Data(b),
+                    _ => ResolvedDescriptor::LocalData { xy, bits: y },
+                });
+                pos += 2;
+            }
             desc => {
                 resolved.push(ResolvedDescriptor::from_descriptor(desc, tables)?);
                 pos += 1;
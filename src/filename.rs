@@ -0,0 +1,239 @@
+//! WMO file naming convention (e.g. "Z__C_RJTD_20210918110000_OBS_AMDS_Rjp_N2_bufr4.bin")
+//!
+//! `pflag_productidentifier_oflag_originator_yyyyMMddhhmmss[_freeformat].type[.compression]`
+
+use serde::Serialize;
+
+use crate::{Error, HeaderSections};
+
+/// Metadata in a file name following the WMO file naming convention
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct WmoFileName {
+    /// How the product identifier is formed: 'T' or 'A' (abbreviated heading), 'W' or 'Z' (local)
+    pub pflag: char,
+    pub product_identifier: String,
+    /// How the originator is formed: 'C' (CCCC)
+    pub oflag: char,
+    /// Originator such as "RJTD"
+    pub originator: String,
+    pub timestamp: FileTimestamp,
+    /// Underscore-separated free format fields (e.g. "OBS", "AMDS", "Rjp", "N2", "bufr4")
+    pub free_format: Vec<String>,
+    /// File type (e.g. "bin")
+    pub file_type: String,
+    /// Compression (e.g. "gz")
+    pub compression: Option<String>,
+}
+
+/// The `yyyyMMddhhmmss` part of a file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FileTimestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl WmoFileName {
+    /// Parse a file name (directories in the path are ignored)
+    pub fn parse(path: &str) -> Result<Self, Error> {
+        let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        let invalid = |reason: &str| {
+            Error::Fatal(format!(
+                "File name {:?} does not follow the WMO file naming convention: {}",
+                name, reason
+            ))
+        };
+
+        let mut extensions = name.split('.');
+        let stem = extensions.next().unwrap_or_default();
+        let Some(file_type) = extensions.next().filter(|t| !t.is_empty()) else {
+            return Err(invalid("no file type"));
+        };
+        let compression = extensions.next().map(str::to_string);
+        if extensions.next().is_some() {
+            return Err(invalid("too many extensions"));
+        }
+
+        let mut fields = stem.split('_');
+        let (Some(pflag), Some(product_identifier), Some(oflag), Some(originator), Some(timestamp)) = (
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+        ) else {
+            return Err(invalid("too few fields"));
+        };
+        let pflag = match pflag {
+            "T" | "A" | "W" | "Z" => pflag.chars().next().unwrap(),
+            _ => return Err(invalid("unknown pflag")),
+        };
+        if matches!(pflag, 'T' | 'A') && product_identifier.len() < 16 {
+            return Err(invalid("product identifier is not an abbreviated heading"));
+        }
+        let oflag = match oflag {
+            "C" => 'C',
+            _ => return Err(invalid("unknown oflag")),
+        };
+        if originator.len() != 4 || !originator.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(invalid("originator is not a location indicator"));
+        }
+        let timestamp =
+            FileTimestamp::parse(timestamp).ok_or_else(|| invalid("invalid timestamp"))?;
+
+        Ok(Self {
+            pflag,
+            product_identifier: product_identifier.to_string(),
+            oflag,
+            originator: originator.to_string(),
+            timestamp,
+            free_format: fields.map(str::to_string).collect(),
+            file_type: file_type.to_string(),
+            compression,
+        })
+    }
+
+    /// TTAAii of the product identifier formed from an abbreviated heading (pflag 'T' or 'A')
+    pub fn ttaaii(&self) -> Option<&str> {
+        match self.pflag {
+            'T' | 'A' => self.product_identifier.get(..6),
+            _ => None,
+        }
+    }
+
+    /// Type of the product, i.e. the first free format field (e.g. "OBS")
+    pub fn product_type(&self) -> Option<&str> {
+        self.free_format.first().map(String::as_str)
+    }
+
+    /// Check that the timestamp agrees with the typical time of the identification section.
+    ///
+    /// The timestamp is often the nominal time of the product, so only the date and the hour
+    /// are compared. Up to edition 3 the typical year is the year of the century.
+    ///
+    /// The originator is not checked against the originating centre, as the crate has no
+    /// table from location indicators (CCCC) to centres.
+    pub fn check_header(&self, header: &HeaderSections) -> Result<(), Error> {
+        let ts = &self.timestamp;
+        let section = &header.identification_section;
+        let year = match header.indicator_section.edition_number {
+            0..=3 => ts.year % 100,
+            _ => ts.year,
+        };
+        let typical = (
+            section.typical_year,
+            section.typical_month,
+            section.typical_day,
+            section.typical_hour,
+        );
+        if (year, ts.month, ts.day, ts.hour) != typical {
+            return Err(Error::Fatal(format!(
+                "Timestamp {:04}-{:02}-{:02} {:02}h of the file name differs from the typical time {:04}-{:02}-{:02} {:02}h",
+                ts.year, ts.month, ts.day, ts.hour, typical.0, typical.1, typical.2, typical.3
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl FileTimestamp {
+    fn parse(s: &str) -> Option<Self> {
+        if s.len() != 14 || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let number = |range: std::ops::Range<usize>| s[range].parse::<u16>().ok();
+        let timestamp = Self {
+            year: number(0..4)?,
+            month: number(4..6)? as u8,
+            day: number(6..8)? as u8,
+            hour: number(8..10)? as u8,
+            minute: number(10..12)? as u8,
+            second: number(12..14)? as u8,
+        };
+        let valid = (1..=12).contains(&timestamp.month)
+            && (1..=31).contains(&timestamp.day)
+            && timestamp.hour <= 24
+            && timestamp.minute < 60
+            && timestamp.second <= 60;
+        valid.then_some(timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_name() {
+        let name =
+            WmoFileName::parse("tests/data/jma/Z__C_RJTD_20210918110000_OBS_AMDS_Rjp_N2_bufr4.bin")
+                .unwrap();
+        assert_eq!(name.pflag, 'Z');
+        assert_eq!(name.product_identifier, "");
+        assert_eq!(name.originator, "RJTD");
+        assert_eq!(
+            name.timestamp,
+            FileTimestamp {
+                year: 2021,
+                month: 9,
+                day: 18,
+                hour: 11,
+                minute: 0,
+                second: 0
+            }
+        );
+        assert_eq!(name.product_type(), Some("OBS"));
+        assert_eq!(name.free_format, ["OBS", "AMDS", "Rjp", "N2", "bufr4"]);
+        assert_eq!(name.file_type, "bin");
+        assert_eq!(name.compression, None);
+        assert_eq!(name.ttaaii(), None);
+
+        let name = WmoFileName::parse("A_ISMC01EDZW160000CCA_C_EDZW_20240416000512_12345.bufr.gz")
+            .unwrap();
+        assert_eq!(name.ttaaii(), Some("ISMC01"));
+        assert_eq!(name.originator, "EDZW");
+        assert_eq!(name.product_type(), Some("12345"));
+        assert_eq!(name.compression.as_deref(), Some("gz"));
+
+        assert!(WmoFileName::parse("ISTC82.dat").is_err());
+        assert!(WmoFileName::parse("Z__C_RJTD_20210918110000_OBS").is_err());
+        assert!(WmoFileName::parse("Z__C_RJTD_20211318110000_OBS.bin").is_err());
+        assert!(WmoFileName::parse("X__C_RJTD_20210918110000_OBS.bin").is_err());
+        assert!(WmoFileName::parse("T_ISMC01_C_EDZW_20240416000512.bin").is_err());
+    }
+
+    #[test]
+    fn test_check_header() {
+        // Edition 3 with the typical time 17-07-31 00:02
+        let mut message = b"BUFR\x00\x00\x00\x03".to_vec();
+        message.extend([0, 0, 18, 0, 0, 34, 0, 0, 2, 0, 13, 0, 17, 7, 31, 0, 2, 0]);
+        message.extend([0, 0, 10, 0, 0, 1, 0x80, 0x01, 0x01, 0]);
+        let header = HeaderSections::read(std::io::Cursor::new(&message)).unwrap();
+
+        let name = WmoFileName::parse("Z__C_RJTD_20170731000000_OBS.bin").unwrap();
+        name.check_header(&header).unwrap();
+        let name = WmoFileName::parse("Z__C_RJTD_20170731010000_OBS.bin").unwrap();
+        assert!(name.check_header(&header).is_err());
+        let name = WmoFileName::parse("Z__C_RJTD_19170731000000_OBS.bin").unwrap();
+        name.check_header(&header).unwrap();
+
+        // The whole year from edition 4
+        message[7] = 4;
+        message.splice(
+            8..26,
+            [
+                0, 0, 22, 0, 0, 34, 0, 0, 0, 0, 2, 0, 0, 13, 0, 0x07, 0xe1, 7, 31, 0, 2, 0,
+            ],
+        );
+        let header = HeaderSections::read(std::io::Cursor::new(&message)).unwrap();
+        let name = WmoFileName::parse("Z__C_RJTD_20170731000000_OBS.bin").unwrap();
+        name.check_header(&header).unwrap();
+        let name = WmoFileName::parse("Z__C_RJTD_19170731000000_OBS.bin").unwrap();
+        assert!(name.check_header(&header).is_err());
+    }
+}
@@ -1,12 +1,23 @@
+mod bitmap;
+mod bulletin;
 mod descriptor;
+mod filename;
+mod operator;
 mod reader;
+mod scanner;
 mod sections;
 pub mod tables;
+pub mod units;
 
+pub use bitmap::BackReferenceKind;
+pub use bulletin::{AbbreviatedHeading, Bulletin, HeadingIndicator};
 pub use descriptor::*;
+pub use filename::{FileTimestamp, WmoFileName};
+pub use operator::{Modifiers, OperatorContext, OperatorHandler};
 pub use reader::*;
+pub use scanner::{FramedMessage, MessageScanner};
 pub use sections::*;
-pub use tables::{TableBEntry, TableDEntry, Tables};
+pub use tables::{CodeFlagEntry, TableBEntry, TableDEntry, Tables};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -16,6 +27,12 @@ pub enum Error {
     Fatal(String),
     #[error("Not supported: {0}")]
     NotSupported(String),
+    #[error("Value out of range: {0}")]
+    OutOfRange(String),
+    #[error("Unit conversion error: {0}")]
+    UnitConversion(String),
+    #[error("Invalid length: {0}")]
+    Length(#[from] LengthError),
 }
 
 impl From<std::io::Error> for Error {
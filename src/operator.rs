@@ -0,0 +1,69 @@
+//! Extension point for centre-local operators (F = 2)
+
+use std::io::Read;
+
+use bitstream_io::{BigEndian, BitRead, BitReader};
+
+use crate::{DataEvent, Error, XY};
+
+/// Changes to the Table B descriptions made by the operators in effect
+#[derive(Debug, Default, Clone)]
+pub struct Modifiers {
+    /// YYY - 128 of 2-01-YYY (change data width)
+    pub width_offset: i8,
+    /// YYY - 128 of 2-02-YYY (change scale)
+    pub scale_offset: i8,
+    /// YYY of 2-07-YYY (increase scale, reference value and data width)
+    pub precision_increase: u8,
+    /// Bit width of CCITT IA5 elements overridden by 2-08-YYY
+    pub character_width: Option<u32>,
+}
+
+/// Handler of an operator that `DataReader` does not know, such as a centre-local one
+///
+/// Handlers are registered with [`crate::DataReader::register_operator_handler`].
+pub trait OperatorHandler {
+    /// Handle the operator 2-X-Y at `idx` of the current sequence.
+    ///
+    /// Returning `None` emits [`DataEvent::OperatorHandled`].
+    fn handle(
+        &mut self,
+        ctx: &mut OperatorContext<'_>,
+        idx: u16,
+        xy: XY,
+    ) -> Result<Option<DataEvent>, Error>;
+}
+
+/// Access to the data section and the reader state given to an [`OperatorHandler`]
+pub struct OperatorContext<'r> {
+    pub(crate) reader: &'r mut dyn BitSource,
+    pub modifiers: &'r mut Modifiers,
+    pub(crate) number_of_subsets: u16,
+    pub(crate) is_compressed: bool,
+}
+
+impl OperatorContext<'_> {
+    /// Read an unsigned integer of up to 64 bits from the data section
+    pub fn read_bits(&mut self, bits: u32) -> Result<u64, Error> {
+        Ok(self.reader.read_bits(bits)?)
+    }
+
+    pub fn number_of_subsets(&self) -> u16 {
+        self.number_of_subsets
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.is_compressed
+    }
+}
+
+/// Object-safe bit reading for `OperatorContext`
+pub(crate) trait BitSource {
+    fn read_bits(&mut self, bits: u32) -> std::io::Result<u64>;
+}
+
+impl<R: Read> BitSource for BitReader<R, BigEndian> {
+    fn read_bits(&mut self, bits: u32) -> std::io::Result<u64> {
+        self.read(bits)
+    }
+}
@@ -1,21 +1,106 @@
 //! Reader for the data section of BUFR files
 
-use std::io::Read;
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom};
 
 use binrw::{BinRead, BinReaderExt};
 use bitstream_io::{BigEndian, BitRead, BitReader};
+use hashbrown::HashMap;
 
+use crate::bitmap::BackReferences;
+use crate::operator::{Modifiers, OperatorContext, OperatorHandler};
 use crate::sections::DataDescriptionSection;
 use crate::tables::{TableBEntry, TableDEntry, Tables};
-use crate::{Error, ResolvedDescriptor, XY, resolve_descriptors};
+use crate::units;
+use crate::{BackReferenceKind, Error, LengthError, ResolvedDescriptor, XY, resolve_descriptors};
+
+/// 0-31-021: Associated field significance
+const ASSOCIATED_FIELD_SIGNIFICANCE: XY = XY { x: 31, y: 21 };
+/// 0-31-031: Data present indicator
+const DATA_PRESENT_INDICATOR: XY = XY { x: 31, y: 31 };
 
 pub struct DataReader<'a, R: Read> {
     data_spec: &'a DataSpec<'a>,
     current_subset_idx: u16,
-    reader: BitReader<R, BigEndian>,
+    reader: BitReader<CountingReader<R>, BigEndian>,
     stack: smallvec::SmallVec<[StackEntry<'a>; 8]>,
-    temporary_operator: Option<XY>,
-    scale_offset: i8,
+    modifiers: Modifiers,
+    /// Bit width of new reference values while 2-03-YYY is in effect
+    reference_value_bits: Option<u8>,
+    reference_values: HashMap<XY, i32>,
+    /// Associated fields (2-04-YYY) in the order they were added
+    associated_fields: smallvec::SmallVec<[AssociatedField; 2]>,
+    /// Number of following data elements affected by 2-21-YYY (data not present)
+    data_not_present: u8,
+    /// Data elements of the current subset which bitmaps may refer back to
+    back_references: BackReferences<(XY, Encoding)>,
+    /// Events already decoded but not yet returned
+    pending: VecDeque<DataEvent>,
+    operator_handlers: HashMap<u8, Box<dyn OperatorHandler + 'a>>,
+    /// Whether to emit `DataEvent::ElementLayout` before each data element
+    verbose: bool,
+    /// Section length in the header of the data section
+    section_length: u32,
+    /// Edition of the message when checking that the data fits exactly in the data section
+    strict: Option<u8>,
+}
+
+/// Reader which counts the bytes read so that bit offsets can be reported
+struct CountingReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+/// Only reports the current position (for `BitReader::position_in_bits`)
+impl<R> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match pos {
+            SeekFrom::Current(0) => Ok(self.position),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Data section can not be seeked",
+            )),
+        }
+    }
+}
+
+/// Effective encoding of a data element after applying the operators in effect
+#[derive(Clone, Copy, Debug)]
+struct Encoding {
+    character: bool,
+    bit_width: u32,
+    ref_value: i64,
+    scale: i8,
+    unit: &'static str,
+    /// Whether all bits set to 1 means a missing value
+    missing: bool,
+}
+
+impl Encoding {
+    /// Plain integer without reference value and scale, which is never missing
+    fn integer(bit_width: u32) -> Self {
+        Self {
+            character: false,
+            bit_width,
+            ref_value: 0,
+            scale: 0,
+            unit: "Numeric",
+            missing: false,
+        }
+    }
+}
+
+struct AssociatedField {
+    bits: u8,
+    significance: Option<u8>,
 }
 
 #[derive(Debug)]
@@ -44,18 +129,61 @@ impl<'a, R: BinReaderExt> DataReader<'a, R> {
         spec: impl Into<&'a DataSpec<'a>>,
     ) -> Result<DataReader<'a, R>, Error> {
         let spec = spec.into();
-        let _data_section_header: DataSectionHeader = reader.read_be()?;
+        let data_section_header: DataSectionHeader = reader.read_be()?;
+        let reader = CountingReader {
+            inner: reader,
+            // Offsets are counted from the start of the data section
+            position: 4,
+        };
         Ok(DataReader {
             data_spec: spec,
             current_subset_idx: 0,
             reader: BitReader::endian(reader, BigEndian),
             stack: smallvec::SmallVec::new(),
-            temporary_operator: None,
-            scale_offset: 0,
+            modifiers: Modifiers::default(),
+            reference_value_bits: None,
+            reference_values: HashMap::new(),
+            associated_fields: smallvec::SmallVec::new(),
+            data_not_present: 0,
+            back_references: BackReferences::default(),
+            pending: VecDeque::new(),
+            operator_handlers: HashMap::new(),
+            verbose: false,
+            section_length: data_section_header.section_length,
+            strict: None,
         })
     }
 }
 
+impl<'a, R: Read> DataReader<'a, R> {
+    /// Register a handler for operators 2-X-YYY with the given X.
+    ///
+    /// Registered handlers take precedence over the built-in operators.
+    pub fn register_operator_handler(&mut self, x: u8, handler: impl OperatorHandler + 'a) {
+        self.operator_handlers.insert(x, Box::new(handler));
+    }
+
+    /// Emit [`DataEvent::ElementLayout`] before each data element for debugging
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// Check that the data of a message of the given edition does not run past the end of the
+    /// data section, and that at most padding is left at the end of it (`None` to disable).
+    ///
+    /// The padding is the rest of the last octet, and up to one more octet until edition 3,
+    /// where sections have an even length. It is skipped when `DataEvent::Eof` is returned,
+    /// so that the end section follows.
+    pub fn set_strict(&mut self, edition: Option<u8>) {
+        self.strict = edition;
+    }
+
+    /// Length of the data section (Section 4) in its header
+    pub fn section_length(&self) -> u32 {
+        self.section_length
+    }
+}
+
 struct StackEntry<'a> {
     descriptors: &'a [ResolvedDescriptor<'a>],
     next: u16,
@@ -64,7 +192,15 @@ struct StackEntry<'a> {
 
 enum StackEntryType {
     Sequence,
-    Replication { remaining: u16, in_item: bool },
+    Replication {
+        remaining: u16,
+        in_item: bool,
+        /// Events of the first item (from its ReplicationItemStart) to repeat
+        /// for the other items, for delayed repetition (0-31-011 and 0-31-012)
+        repetition: Option<Vec<DataEvent>>,
+        /// Number of elements in the backward reference list when the current item started
+        first_element: usize,
+    },
 }
 
 impl<'a> StackEntry<'a> {
@@ -76,13 +212,19 @@ impl<'a> StackEntry<'a> {
         }
     }
 
-    fn new_replication(descriptors: &'a [ResolvedDescriptor<'a>], count: u16) -> Self {
+    fn new_replication(
+        descriptors: &'a [ResolvedDescriptor<'a>],
+        count: u16,
+        is_repetition: bool,
+    ) -> Self {
         Self {
             descriptors,
             next: descriptors.len() as u16,
             entry_type: StackEntryType::Replication {
                 remaining: count,
                 in_item: false,
+                repetition: is_repetition.then(Vec::new),
+                first_element: 0,
             },
         }
     }
@@ -99,7 +241,7 @@ pub struct DataSectionHeader {
     pub section_length: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum DataEvent {
     SubsetStart(u16),
     SubsetEnd,
@@ -121,36 +263,163 @@ pub enum DataEvent {
         x: u8,
         value: i32,
     },
+    /// A new reference value defined by operator 2-03-YYY
+    ReferenceValueDefined {
+        idx: u16,
+        xy: XY,
+        reference_value: i32,
+    },
+    /// An associated field (operator 2-04-YYY) preceding the next data element
+    AssociatedField {
+        idx: u16,
+        significance: Option<u8>,
+        bits: u8,
+        value: u32,
+    },
+    CompressedAssociatedField {
+        idx: u16,
+        significance: Option<u8>,
+        bits: u8,
+        values: Vec<u32>,
+    },
+    /// The next data event holds a value which refers back to a previous data element
+    /// through a data present bitmap (operators 2-22 to 2-37).
+    ///
+    /// `position` counts the data elements of the subset from zero, including delayed
+    /// replication factors but not the values following 2-XX-255 markers.
+    BackReference {
+        kind: BackReferenceKind,
+        position: usize,
+        xy: XY,
+    },
+    /// A data element which is not present in the data section (operator 2-21-YYY)
+    NotPresent {
+        idx: u16,
+        xy: XY,
+    },
+    /// Values produced by a registered [`OperatorHandler`]
+    Custom {
+        idx: u16,
+        xy: XY,
+        values: Vec<Value>,
+    },
+    /// Characters inserted by operator 2-05-YYY
+    Characters {
+        idx: u16,
+        value: String,
+    },
+    CompressedCharacters {
+        idx: u16,
+        values: Vec<Value>,
+    },
+    /// Position and encoding of the following data element, emitted only in verbose mode
+    ElementLayout {
+        idx: u16,
+        xy: XY,
+        /// Offset from the start of the data section (the local reference value if compressed)
+        bit_offset: u64,
+        bit_width: u32,
+        scale: i8,
+        reference_value: i64,
+    },
+    /// Unknown local element (operator 2-06-YYY) whose bits were skipped
+    SkippedData {
+        idx: u16,
+        xy: XY,
+        bits: u8,
+    },
     Data {
         idx: u16,
         xy: XY,
+        /// Unit of the element in Table B
+        unit: &'static str,
+        /// Scale in effect (the value is multiplied by 10^-scale)
+        scale: i8,
         value: Value,
+        /// Raw integer before applying the reference value and the scale (`None` for characters)
+        raw: Option<u64>,
     },
     CompressedData {
         idx: u16,
         xy: XY,
+        unit: &'static str,
+        scale: i8,
         values: Vec<Value>,
+        raw_values: Option<Vec<u64>>,
     },
     Eof,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum Value {
     Missing,
-    Decimal(i32, i8),
-    Integer(i32),
+    Decimal(i64, i8),
+    Integer(i64),
     String(String),
 }
 
+impl Value {
+    pub fn is_missing(&self) -> bool {
+        matches!(self, Value::Missing)
+    }
+
+    /// The numeric value as `f64` (`None` if missing or a string)
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            // Dividing by an exact power of ten keeps e.g. 27315 * 10^-2 as 273.15
+            Value::Decimal(v, s) if s < 0 => Some(v as f64 / 10f64.powi(-(s as i32))),
+            Value::Decimal(v, s) => Some(v as f64 * 10f64.powi(s as i32)),
+            Value::Integer(v) => Some(v as f64),
+            _ => None,
+        }
+    }
+
+    /// The numeric value as an exact decimal `(mantissa, exponent)`, i.e. mantissa * 10^exponent
+    pub fn as_decimal(&self) -> Option<(i64, i8)> {
+        match *self {
+            Value::Decimal(v, s) => Some((v, s)),
+            Value::Integer(v) => Some((v, 0)),
+            _ => None,
+        }
+    }
+
+    /// The numeric value as `i64` if it is an integer (including decimals with positive scales)
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Decimal(v, s) if s < 0 => {
+                let divisor = 10i64.checked_pow(-(s as i32) as u32)?;
+                (v % divisor == 0).then(|| v / divisor)
+            }
+            Value::Decimal(v, s) => v.checked_mul(10i64.checked_pow(s as u32)?),
+            Value::Integer(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The numeric value converted from `unit` (e.g. the unit of the data event) into `target`
+    pub fn convert_unit(&self, unit: &str, target: &str) -> Result<Option<f64>, Error> {
+        self.as_f64()
+            .map(|v| units::convert(v, unit, target))
+            .transpose()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Debug for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Missing => write!(f, "Missing"),
-            &Value::Decimal(v, s) => {
+            &Value::Decimal(_, s) => {
                 write!(
                     f,
                     "{:.1$}",
-                    v as f64 * 10f64.powi(s as i32),
+                    self.as_f64().unwrap_or_default(),
                     if s < 0 { -s } else { 0 } as usize
                 )
             }
@@ -162,6 +431,47 @@ impl std::fmt::Debug for Value {
 
 impl<'a, R: Read> DataReader<'a, R> {
     pub fn read_event(&mut self) -> Result<DataEvent, Error> {
+        let event = match self.pending.pop_front() {
+            Some(event) => event,
+            None => self.next_event()?,
+        };
+        if let Some(edition) = self.strict {
+            self.check_section_end(edition, matches!(event, DataEvent::Eof))?;
+        }
+        self.record_repetition(&event);
+        Ok(event)
+    }
+
+    /// Check the position against the section length, skipping the padding at the end
+    fn check_section_end(&mut self, edition: u8, eof: bool) -> Result<(), Error> {
+        let section_bits = self.section_length as u64 * 8;
+        let bits_read = self.reader.position_in_bits()?;
+        if bits_read > section_bits {
+            return Err(LengthError::DataSectionOverrun {
+                section_length: self.section_length,
+                bits_read,
+            }
+            .into());
+        }
+        if eof {
+            let unused_bits = section_bits - bits_read;
+            let max_padding_bits = if edition <= 3 { 15 } else { 7 };
+            if unused_bits > max_padding_bits {
+                return Err(LengthError::DataSectionUnderrun {
+                    section_length: self.section_length,
+                    bits_read,
+                }
+                .into());
+            }
+            self.reader.byte_align();
+            if unused_bits >= 8 {
+                self.reader.skip(8)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> Result<DataEvent, Error> {
         if self.stack.is_empty() {
             if self.data_spec.is_compressed {
                 if self.current_subset_idx > 0 {
@@ -173,6 +483,7 @@ impl<'a, R: Read> DataReader<'a, R> {
 
             self.stack
                 .push(StackEntry::new_sequence(&self.data_spec.root_descriptors));
+            self.back_references = BackReferences::default();
             let subset_idx = self.current_subset_idx;
             self.current_subset_idx += 1;
             if self.data_spec.is_compressed {
@@ -184,23 +495,56 @@ impl<'a, R: Read> DataReader<'a, R> {
         self.process_next_descriptor()
     }
 
+    /// Keep the events of the first item of delayed repetitions
+    fn record_repetition(&mut self, event: &DataEvent) {
+        // Bit offsets would be wrong for the repeated items
+        if matches!(event, DataEvent::ElementLayout { .. }) {
+            return;
+        }
+        for entry in &mut self.stack {
+            if let StackEntryType::Replication {
+                in_item: true,
+                repetition: Some(events),
+                ..
+            } = &mut entry.entry_type
+            {
+                events.push(event.clone());
+            }
+        }
+    }
+
     fn process_next_descriptor(&mut self) -> Result<DataEvent, Error> {
         let top = self.stack.last_mut().expect("Stack should not be empty");
-        if let StackEntryType::Replication { remaining, in_item } = &mut top.entry_type {
-            if top.next as usize >= top.descriptors.len() {
-                if *in_item {
-                    *in_item = false;
-                    return Ok(DataEvent::ReplicationItemEnd);
-                }
-                if *remaining > 0 {
-                    *remaining -= 1;
-                    top.next = 0;
-                    *in_item = true;
-                    return Ok(DataEvent::ReplicationItemStart);
-                } else {
-                    self.stack.pop();
-                    return Ok(DataEvent::ReplicationEnd);
+        if let StackEntryType::Replication {
+            remaining,
+            in_item,
+            repetition,
+            first_element,
+        } = &mut top.entry_type
+            && top.next as usize >= top.descriptors.len()
+        {
+            if *in_item {
+                *in_item = false;
+                if let Some(events) = repetition.take() {
+                    // Repeat the first item without reading the data section
+                    let times = std::mem::take(remaining);
+                    for _ in 0..times {
+                        self.pending.extend(events.iter().cloned());
+                        self.pending.push_back(DataEvent::ReplicationItemEnd);
+                    }
+                    self.back_references.repeat(*first_element, times as usize);
                 }
+                return Ok(DataEvent::ReplicationItemEnd);
+            }
+            if *remaining > 0 {
+                *remaining -= 1;
+                top.next = 0;
+                *in_item = true;
+                *first_element = self.back_references.element_count();
+                return Ok(DataEvent::ReplicationItemStart);
+            } else {
+                self.stack.pop();
+                return Ok(DataEvent::ReplicationEnd);
             }
         };
 
@@ -218,13 +562,26 @@ impl<'a, R: Read> DataReader<'a, R> {
         let idx = top.next;
         top.next += 1;
         match current_desc {
-            ResolvedDescriptor::Data(b) => self.handle_data_descriptor(idx, b),
+            ResolvedDescriptor::Data(b) => match self.reference_value_bits {
+                Some(bits) => self.handle_reference_value_definition(idx, b, bits),
+                None => self.handle_data_descriptor(idx, b),
+            },
             ResolvedDescriptor::Replication {
                 y,
                 descriptors,
                 delayed_bits,
-            } => self.handle_replication_descriptor(idx, *y, descriptors, *delayed_bits),
+                delayed_factor,
+            } => self.handle_replication_descriptor(
+                idx,
+                *y,
+                descriptors,
+                *delayed_bits,
+                *delayed_factor,
+            ),
             ResolvedDescriptor::Operator(xy) => self.handle_operator_descriptor(idx, *xy),
+            ResolvedDescriptor::LocalData { xy, bits } => {
+                self.handle_local_data_descriptor(idx, *xy, *bits)
+            }
             ResolvedDescriptor::Sequence(d, elements) => {
                 self.handle_sequence_descriptor(idx, d, elements)
             }
@@ -233,90 +590,410 @@ impl<'a, R: Read> DataReader<'a, R> {
 
     // f = 0
     fn handle_data_descriptor(&mut self, idx: u16, b: &TableBEntry) -> Result<DataEvent, Error> {
-        let (bit_width, ref_value, scale) = (
-            b.bits as u32,
-            b.reference_value,
-            (b.scale as i16 + self.scale_offset as i16) as i8,
-        );
-        match bit_width {
-            0..=32 => {
-                if self.data_spec.is_compressed {
-                    let local_ref_value: u32 = self.reader.read(bit_width)?;
-                    let nbinc: u8 = self.reader.read(6)?;
+        let is_indicator = b.xy == DATA_PRESENT_INDICATOR && self.back_references.is_collecting();
+        if !is_indicator {
+            self.back_references.finish_bitmap()?;
+        }
+        // Only the elements of classes 1-9 and 31 are present after 2-21-YYY
+        if self.data_not_present > 0 {
+            self.data_not_present -= 1;
+            if !matches!(b.xy.x, 1..=9 | 31) {
+                self.back_references.push((b.xy, self.element_encoding(b)?));
+                return Ok(DataEvent::NotPresent { idx, xy: b.xy });
+            }
+        }
+        // Class 31 elements are never preceded by associated fields
+        if b.xy.x != 31 {
+            self.read_associated_fields(idx)?;
+        }
+        // e.g. quality information (class 33) following 2-22-000 refers back to the data elements
+        if let Some(kind) = self.back_references.kind()
+            && kind.applies_to_element(b.xy)
+            && let Some((position, &(xy, _))) = self.back_references.next_target()
+        {
+            self.pending
+                .push_back(DataEvent::BackReference { kind, position, xy });
+        }
 
-                    Ok(DataEvent::CompressedData {
-                        idx,
-                        xy: b.xy,
-                        values: if nbinc == 0 {
-                            // All values are the same if nbinc == 0
-                            let v = if local_ref_value == ((1u64 << bit_width) - 1) as u32 {
-                                Value::Missing
-                            } else if scale == 0 {
-                                Value::Integer(local_ref_value as i32 + ref_value)
-                            } else {
-                                Value::Decimal(
-                                    (local_ref_value as i64 + ref_value as i64) as i32,
-                                    -scale,
-                                )
-                            };
-                            vec![v; self.data_spec.number_of_subsets as usize]
-                        } else {
-                            (0..self.data_spec.number_of_subsets)
-                                .map(|_| {
-                                    let inc: u32 = self.reader.read(nbinc as u32)?;
-                                    let v_raw = local_ref_value + inc;
-                                    Ok(if v_raw == ((1u64 << bit_width) - 1) as u32 {
-                                        Value::Missing
-                                    } else if scale == 0 {
-                                        Value::Integer(v_raw as i32 + ref_value)
-                                    } else {
-                                        Value::Decimal(
-                                            (v_raw as i64 + ref_value as i64) as i32,
-                                            -scale,
-                                        )
-                                    })
-                                })
-                                .collect::<std::io::Result<Vec<Value>>>()?
-                        },
-                    })
-                } else {
-                    let v_raw: u32 = self.reader.read(bit_width)?;
-                    let value = if v_raw == ((1u64 << bit_width) - 1) as u32 {
-                        Value::Missing
-                    } else if scale == 0 {
-                        Value::Integer(v_raw as i32 + ref_value)
-                    } else {
-                        Value::Decimal((v_raw as i64 + ref_value as i64) as i32, -scale)
-                    };
-                    Ok(DataEvent::Data {
-                        idx,
-                        xy: b.xy,
-                        value,
-                    })
+        let encoding = self.element_encoding(b)?;
+        let event = self.read_element(idx, b.xy, encoding)?;
+        self.back_references.push((b.xy, encoding));
+        if b.xy == ASSOCIATED_FIELD_SIGNIFICANCE {
+            if let (Some(field), Some(Value::Integer(v))) =
+                (self.associated_fields.last_mut(), first_value(&event))
+            {
+                field.significance = Some(*v as u8);
+            }
+        } else if is_indicator {
+            // 0 means that the data are present
+            self.back_references
+                .push_bit(first_value(&event) == Some(&Value::Integer(0)));
+        }
+        Ok(self.emit(event))
+    }
+
+    /// Effective encoding of a data element after applying the operators in effect
+    fn element_encoding(&self, b: &TableBEntry) -> Result<Encoding, Error> {
+        if b.is_character() {
+            return Ok(Encoding {
+                character: true,
+                bit_width: self.modifiers.character_width.unwrap_or(b.bits as u32),
+                ref_value: 0,
+                scale: 0,
+                unit: b.unit,
+                missing: true,
+            });
+        }
+
+        // 2-01, 2-02 and 2-07 do not apply to character data, code or flag tables
+        if b.is_code_or_flag_table() {
+            return Ok(Encoding {
+                character: false,
+                bit_width: b.bits as u32,
+                ref_value: b.reference_value as i64,
+                scale: b.scale,
+                unit: b.unit,
+                missing: b.has_missing_value(b.bits as u32),
+            });
+        }
+        let increase = self.modifiers.precision_increase as i32;
+        let bit_width =
+            b.bits as i32 + self.modifiers.width_offset as i32 + (10 * increase + 2) / 3;
+        if bit_width <= 0 {
+            return Err(Error::Fatal(format!(
+                "Invalid data width {} for {:?} after applying operators 2-01 and 2-07",
+                bit_width, b.xy
+            )));
+        }
+        let ref_value = self
+            .reference_values
+            .get(&b.xy)
+            .copied()
+            .unwrap_or(b.reference_value) as i64;
+        // A zero reference value stays zero however large the factor is
+        let ref_value = match ref_value {
+            0 => Some(0),
+            _ => 10i64
+                .checked_pow(increase as u32)
+                .and_then(|factor| ref_value.checked_mul(factor)),
+        };
+        let Some(ref_value) = ref_value else {
+            return Err(Error::OutOfRange(format!(
+                "Reference value of {:?} is too large after applying operator 2-07",
+                b.xy
+            )));
+        };
+        let scale = b.scale as i16 + self.modifiers.scale_offset as i16 + increase as i16;
+        let Some(scale) = i8::try_from(scale).ok().filter(|&scale| scale != i8::MIN) else {
+            return Err(Error::OutOfRange(format!(
+                "Scale {} of {:?} after applying operators 2-02 and 2-07",
+                scale, b.xy
+            )));
+        };
+        Ok(Encoding {
+            character: false,
+            bit_width: bit_width as u32,
+            ref_value,
+            scale,
+            unit: b.unit,
+            missing: b.has_missing_value(bit_width as u32),
+        })
+    }
+
+    /// Read the value(s) of a data element with the given encoding
+    fn read_element(&mut self, idx: u16, xy: XY, encoding: Encoding) -> Result<DataEvent, Error> {
+        let Encoding {
+            character,
+            bit_width,
+            ref_value,
+            scale,
+            unit,
+            missing,
+        } = encoding;
+        if self.verbose {
+            self.pending.push_back(DataEvent::ElementLayout {
+                idx,
+                xy,
+                bit_offset: self.reader.position_in_bits()?,
+                bit_width,
+                scale,
+                reference_value: ref_value,
+            });
+        }
+        if character {
+            return Ok(if self.data_spec.is_compressed {
+                DataEvent::CompressedData {
+                    idx,
+                    xy,
+                    unit,
+                    scale,
+                    values: self.read_compressed_strings(bit_width)?,
+                    raw_values: None,
+                }
+            } else {
+                DataEvent::Data {
+                    idx,
+                    xy,
+                    unit,
+                    scale,
+                    value: character_value(self.read_bytes(bit_width)?)?,
+                    raw: None,
                 }
+            });
+        }
+
+        if !(1..=64).contains(&bit_width) {
+            return Err(Error::Fatal(format!("Unsupported bit width {}", bit_width)));
+        }
+        let value_of = |raw: u64| {
+            if missing && raw == u64::MAX >> (64 - bit_width) {
+                return Ok(Value::Missing);
             }
-            _ if bit_width % 8 == 0 => {
-                let Ok(s) = String::from_utf8(self.reader.read_to_vec((bit_width / 8) as usize)?)
-                else {
-                    return Err(Error::Fatal(format!(
-                        "Failed to parse character string with bit width {}",
-                        bit_width
-                    )));
-                };
-                if self.data_spec.is_compressed {
-                    Err(Error::NotSupported(
-                        "Compressed data for characters not implemented yet".to_string(),
+            let Some(value) = i64::try_from(raw)
+                .ok()
+                .and_then(|raw| raw.checked_add(ref_value))
+            else {
+                return Err(Error::OutOfRange(format!(
+                    "Value of {:?} does not fit in i64 (raw value {}, reference value {})",
+                    xy, raw, ref_value
+                )));
+            };
+            Ok(if scale == 0 {
+                Value::Integer(value)
+            } else {
+                Value::Decimal(value, -scale)
+            })
+        };
+        Ok(if self.data_spec.is_compressed {
+            let raw_values = self.read_compressed_raw(bit_width, missing)?;
+            DataEvent::CompressedData {
+                idx,
+                xy,
+                unit,
+                scale,
+                values: raw_values
+                    .iter()
+                    .map(|&raw| value_of(raw))
+                    .collect::<Result<_, _>>()?,
+                raw_values: Some(raw_values),
+            }
+        } else {
+            let raw: u64 = self.reader.read(bit_width)?;
+            DataEvent::Data {
+                idx,
+                xy,
+                unit,
+                scale,
+                value: value_of(raw)?,
+                raw: Some(raw),
+            }
+        })
+    }
+
+    fn read_string(&mut self, bit_width: u32) -> Result<String, Error> {
+        let bytes = self.read_bytes(bit_width)?;
+        let Ok(s) = String::from_utf8(bytes) else {
+            return Err(Error::Fatal(format!(
+                "Failed to parse character string with bit width {}",
+                bit_width
+            )));
+        };
+        Ok(s)
+    }
+
+    fn read_bytes(&mut self, bit_width: u32) -> Result<Vec<u8>, Error> {
+        if !bit_width.is_multiple_of(8) {
+            return Err(Error::Fatal(format!(
+                "Unsupported bit width {} for character string",
+                bit_width
+            )));
+        }
+        Ok(self.reader.read_to_vec((bit_width / 8) as usize)?)
+    }
+
+    /// Read character strings of all subsets in the compressed layout
+    /// (local reference string, 6-bit NBINC in bytes and a string per subset)
+    fn read_compressed_strings(&mut self, bit_width: u32) -> Result<Vec<Value>, Error> {
+        let local_ref_value = self.read_bytes(bit_width)?;
+        let nbinc: u8 = self.reader.read(6)?;
+        if nbinc == 0 {
+            // All values are the same if nbinc == 0
+            let value = character_value(local_ref_value)?;
+            return Ok(vec![value; self.data_spec.number_of_subsets as usize]);
+        }
+        (0..self.data_spec.number_of_subsets)
+            .map(|_| character_value(self.read_bytes(nbinc as u32 * 8)?))
+            .collect()
+    }
+
+    /// Return the event in order, after the events that are already pending
+    fn emit(&mut self, event: DataEvent) -> DataEvent {
+        match self.pending.pop_front() {
+            Some(first) => {
+                self.pending.push_back(event);
+                first
+            }
+            None => event,
+        }
+    }
+
+    /// Read the associated fields preceding a data element into the pending events
+    fn read_associated_fields(&mut self, idx: u16) -> Result<(), Error> {
+        for i in 0..self.associated_fields.len() {
+            let AssociatedField { bits, significance } = self.associated_fields[i];
+            let event = if self.data_spec.is_compressed {
+                DataEvent::CompressedAssociatedField {
+                    idx,
+                    significance,
+                    bits,
+                    values: self
+                        .read_compressed_raw(bits as u32, false)?
+                        .into_iter()
+                        .map(|v| v as u32)
+                        .collect(),
+                }
+            } else {
+                DataEvent::AssociatedField {
+                    idx,
+                    significance,
+                    bits,
+                    value: self.reader.read(bits as u32)?,
+                }
+            };
+            self.pending.push_back(event);
+        }
+        Ok(())
+    }
+
+    /// Read raw integers of all subsets in the compressed layout
+    /// (local reference, 6-bit NBINC and increments)
+    ///
+    /// If `missing` is set, increments with all bits set to 1 are returned as all ones of `bit_width`.
+    fn read_compressed_raw(&mut self, bit_width: u32, missing: bool) -> Result<Vec<u64>, Error> {
+        let local_ref_value: u64 = self.reader.read(bit_width)?;
+        let nbinc: u8 = self.reader.read(6)?;
+        if nbinc == 0 {
+            return Ok(vec![
+                local_ref_value;
+                self.data_spec.number_of_subsets as usize
+            ]);
+        }
+        (0..self.data_spec.number_of_subsets)
+            .map(|_| {
+                let inc: u64 = self.reader.read(nbinc as u32)?;
+                if missing && inc == u64::MAX >> (64 - nbinc as u32) {
+                    return Ok(u64::MAX >> (64 - bit_width));
+                }
+                local_ref_value.checked_add(inc).ok_or_else(|| {
+                    Error::OutOfRange(format!(
+                        "Increment {} overflows the local reference value {}",
+                        inc, local_ref_value
                     ))
-                } else {
-                    Ok(DataEvent::Data {
-                        idx,
-                        xy: b.xy,
-                        value: Value::String(s),
-                    })
+                })
+            })
+            .collect()
+    }
+
+    // 2-23-255, 2-24-255, 2-25-255 and 2-32-255
+    fn handle_referenced_value(
+        &mut self,
+        idx: u16,
+        kind: BackReferenceKind,
+    ) -> Result<DataEvent, Error> {
+        self.back_references.finish_bitmap()?;
+        if self.back_references.kind() != Some(kind) {
+            return Err(Error::Fatal(format!(
+                "{:?} marker appears without its operator",
+                kind
+            )));
+        }
+        let Some((position, &(xy, encoding))) = self.back_references.next_target() else {
+            return Err(Error::Fatal(format!(
+                "No data element left to be referred to by {:?} marker",
+                kind
+            )));
+        };
+        let encoding = match kind {
+            // Difference statistical values have one more bit and a reference value of -2^width
+            BackReferenceKind::DifferenceStatistics
+                if !encoding.character && encoding.bit_width < 63 =>
+            {
+                Encoding {
+                    bit_width: encoding.bit_width + 1,
+                    ref_value: -(1 << encoding.bit_width),
+                    ..encoding
                 }
             }
-            _ => Err(Error::Fatal(format!("Unsupported bit width {}", bit_width))),
+            BackReferenceKind::DifferenceStatistics if encoding.character => {
+                return Err(Error::Fatal(format!(
+                    "Difference statistical values of character data {:?}",
+                    xy
+                )));
+            }
+            BackReferenceKind::DifferenceStatistics => {
+                return Err(Error::OutOfRange(format!(
+                    "Difference statistical values of {:?} with {} bits",
+                    xy, encoding.bit_width
+                )));
+            }
+            _ => encoding,
+        };
+        self.pending
+            .push_back(DataEvent::BackReference { kind, position, xy });
+        let event = self.read_element(idx, xy, encoding)?;
+        Ok(self.emit(event))
+    }
+
+    // f = 0, unknown local descriptor following 2-06-YYY
+    fn handle_local_data_descriptor(
+        &mut self,
+        idx: u16,
+        xy: XY,
+        bits: u8,
+    ) -> Result<DataEvent, Error> {
+        self.back_references
+            .push((xy, Encoding::integer(bits as u32)));
+        self.reader.skip(bits as u32)?;
+        if self.data_spec.is_compressed {
+            let nbinc: u8 = self.reader.read(6)?;
+            self.reader
+                .skip(nbinc as u32 * self.data_spec.number_of_subsets as u32)?;
         }
+        Ok(DataEvent::SkippedData { idx, xy, bits })
+    }
+
+    // f = 0, while 2-03-YYY is in effect
+    fn handle_reference_value_definition(
+        &mut self,
+        idx: u16,
+        b: &TableBEntry,
+        bits: u8,
+    ) -> Result<DataEvent, Error> {
+        let raw: u32 = self.reader.read(bits as u32)?;
+        if self.data_spec.is_compressed {
+            // The new reference value is common to all subsets
+            let nbinc: u8 = self.reader.read(6)?;
+            if nbinc != 0 {
+                return Err(Error::Fatal(format!(
+                    "New reference value for {:?} must be identical in all subsets",
+                    b.xy
+                )));
+            }
+        }
+        // Negative values are represented by setting the left-most bit
+        let sign_bit = 1u32 << (bits - 1);
+        let magnitude = (raw & (sign_bit - 1)) as i32;
+        let reference_value = if raw & sign_bit != 0 {
+            -magnitude
+        } else {
+            magnitude
+        };
+        self.reference_values.insert(b.xy, reference_value);
+        Ok(DataEvent::ReferenceValueDefined {
+            idx,
+            xy: b.xy,
+            reference_value,
+        })
     }
 
     // f = 1
@@ -326,22 +1003,111 @@ impl<'a, R: Read> DataReader<'a, R> {
         y: u8,
         elements: &'a [ResolvedDescriptor<'_>],
         delayed_bits: u8,
+        delayed_factor: Option<XY>,
     ) -> Result<DataEvent, Error> {
         let count = match y {
+            0 if self.data_spec.is_compressed => {
+                // The factor is compressed as well and must be the same in all subsets
+                let values = self.read_compressed_raw(delayed_bits as u32, false)?;
+                let first = values.first().copied().unwrap_or_default();
+                if values.iter().any(|&v| v != first) {
+                    return Err(Error::Fatal(format!(
+                        "Delayed replication factor differs between subsets: {:?}",
+                        values
+                    )));
+                }
+                first as u16
+            }
             0 => self.reader.read::<u16>(delayed_bits as u32)?,
             _ => y as u16,
         };
-        self.stack
-            .push(StackEntry::new_replication(elements, count));
+        if let Some(xy) = delayed_factor {
+            // Delayed replication factors are data elements as well
+            self.back_references
+                .push((xy, Encoding::integer(delayed_bits as u32)));
+        }
+        self.stack.push(StackEntry::new_replication(
+            elements,
+            count,
+            delayed_factor.is_some_and(|xy| xy.x == 31 && matches!(xy.y, 11 | 12)),
+        ));
         Ok(DataEvent::ReplicationStart { idx, count })
     }
 
     // f = 2
     fn handle_operator_descriptor(&mut self, idx: u16, xy: XY) -> Result<DataEvent, Error> {
+        if let Some(handler) = self.operator_handlers.get_mut(&xy.x) {
+            let mut ctx = OperatorContext {
+                reader: &mut self.reader,
+                modifiers: &mut self.modifiers,
+                number_of_subsets: self.data_spec.number_of_subsets,
+                is_compressed: self.data_spec.is_compressed,
+            };
+            return Ok(match handler.handle(&mut ctx, idx, xy)? {
+                Some(event) => event,
+                None => DataEvent::OperatorHandled {
+                    idx,
+                    x: xy.x,
+                    value: xy.y as i32,
+                },
+            });
+        }
+
         match (xy.x, xy.y) {
-            (2, 0) => self.scale_offset = 0,
-            (2, y) => self.scale_offset = ((y as i16) - 128) as i8,
-            (6, _) => self.temporary_operator = Some(xy),
+            (1, 0) => self.modifiers.width_offset = 0,
+            (1, y) => self.modifiers.width_offset = ((y as i16) - 128) as i8,
+            (2, 0) => self.modifiers.scale_offset = 0,
+            (2, y) => self.modifiers.scale_offset = ((y as i16) - 128) as i8,
+            (3, 0) => {
+                self.reference_value_bits = None;
+                self.reference_values.clear();
+            }
+            (3, 255) => self.reference_value_bits = None,
+            (3, y) if y <= 32 => self.reference_value_bits = Some(y),
+            (4, 0) => {
+                self.associated_fields.pop();
+            }
+            (4, y) if y <= 32 => self.associated_fields.push(AssociatedField {
+                bits: y,
+                significance: None,
+            }),
+            (5, y) => {
+                return Ok(if self.data_spec.is_compressed {
+                    DataEvent::CompressedCharacters {
+                        idx,
+                        values: self.read_compressed_strings(y as u32 * 8)?,
+                    }
+                } else {
+                    DataEvent::Characters {
+                        idx,
+                        value: self.read_string(y as u32 * 8)?,
+                    }
+                });
+            }
+            (6, _) => {}
+            (7, y) => self.modifiers.precision_increase = y,
+            (8, 0) => self.modifiers.character_width = None,
+            (8, y) => self.modifiers.character_width = Some(y as u32 * 8),
+            (21, y) => self.data_not_present = y,
+            (22..=25 | 32 | 41..=43, 0) => {
+                if let Some(kind) = BackReferenceKind::from_operator(xy.x) {
+                    self.back_references.begin(kind);
+                }
+            }
+            (23 | 24 | 25 | 32, 255) => {
+                if let Some(kind) = BackReferenceKind::from_operator(xy.x) {
+                    return self.handle_referenced_value(idx, kind);
+                }
+            }
+            (41..=43, 255) => {
+                if let Some(kind) = BackReferenceKind::from_operator(xy.x) {
+                    self.back_references.end(kind);
+                }
+            }
+            (35, 0) => self.back_references.cancel(),
+            (36, 0) => self.back_references.define_bitmap(),
+            (37, 0) => self.back_references.reuse_bitmap()?,
+            (37, 255) => self.back_references.cancel_reuse(),
             _ => {
                 return Err(Error::NotSupported(format!(
                     "Operator descriptor {:#?} not supported yet.",
@@ -368,9 +1134,1059 @@ impl<'a, R: Read> DataReader<'a, R> {
     }
 }
 
+/// Interpret CCITT IA5 character data (all bits set to 1 means missing)
+fn character_value(bytes: Vec<u8>) -> Result<Value, Error> {
+    if bytes.iter().all(|&b| b == 0xff) {
+        return Ok(Value::Missing);
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(Value::String(s)),
+        Err(_) => Err(Error::Fatal("Failed to parse character string".to_string())),
+    }
+}
+
+/// The value of a data event (the first subset for compressed data)
+fn first_value(event: &DataEvent) -> Option<&Value> {
+    match event {
+        DataEvent::Data { value, .. } => Some(value),
+        DataEvent::CompressedData { values, .. } => values.first(),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
+    use bitstream_io::{BitWrite, BitWriter};
+
     use super::*;
+    use crate::Descriptor;
+
+    /// Build a data section (Section 4) from (bit width, value) pairs
+    fn make_data_section(fields: &[(u32, u64)]) -> Vec<u8> {
+        let mut body = BitWriter::endian(Vec::new(), BigEndian);
+        for &(bits, value) in fields {
+            body.write::<u64>(bits, value).unwrap();
+        }
+        body.byte_align().unwrap();
+        let body = body.into_writer();
+        let len = body.len() as u32 + 4;
+        let mut section = vec![(len >> 16) as u8, (len >> 8) as u8, len as u8, 0];
+        section.extend(body);
+        section
+    }
+
+    /// Decode every event until Eof
+    fn read_all_events(
+        descriptors: &[Descriptor],
+        number_of_subsets: u16,
+        is_compressed: bool,
+        fields: &[(u32, u64)],
+    ) -> Result<Vec<DataEvent>, Error> {
+        let tables = Tables::default();
+        read_all_events_with_tables(
+            &tables,
+            descriptors,
+            number_of_subsets,
+            is_compressed,
+            fields,
+            |_| {},
+        )
+    }
+
+    /// Decode every event until Eof, with the reader set up by `configure`
+    fn read_all_events_with_tables(
+        tables: &Tables,
+        descriptors: &[Descriptor],
+        number_of_subsets: u16,
+        is_compressed: bool,
+        fields: &[(u32, u64)],
+        configure: impl FnOnce(&mut DataReader<'_, &mut Cursor<Vec<u8>>>),
+    ) -> Result<Vec<DataEvent>, Error> {
+        let mut section = Cursor::new(make_data_section(fields));
+        read_section_events(
+            tables,
+            descriptors,
+            number_of_subsets,
+            is_compressed,
+            &mut section,
+            configure,
+        )
+    }
+
+    /// Decode every event of a data section until Eof, leaving the cursor after the last read
+    fn read_section_events(
+        tables: &Tables,
+        descriptors: &[Descriptor],
+        number_of_subsets: u16,
+        is_compressed: bool,
+        section: &mut Cursor<Vec<u8>>,
+        configure: impl FnOnce(&mut DataReader<'_, &mut Cursor<Vec<u8>>>),
+    ) -> Result<Vec<DataEvent>, Error> {
+        let spec = DataSpec {
+            number_of_subsets,
+            is_compressed,
+            root_descriptors: resolve_descriptors(tables, descriptors)?,
+        };
+        let mut reader = DataReader::new(section, &spec)?;
+        configure(&mut reader);
+        let mut events = vec![];
+        loop {
+            match reader.read_event()? {
+                DataEvent::Eof => break,
+                ev => events.push(ev),
+            }
+        }
+        Ok(events)
+    }
+
+    /// Collect the values of all data events
+    fn data_values(events: &[DataEvent]) -> Vec<Value> {
+        events
+            .iter()
+            .flat_map(|ev| match ev {
+                DataEvent::Data { value, .. } => vec![value.clone()],
+                DataEvent::CompressedData { values, .. } => values.clone(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    const fn desc(f: u8, x: u8, y: u8) -> Descriptor {
+        Descriptor { f, x, y }
+    }
+
+    #[test]
+    fn test_change_data_width() {
+        // 0-12-101 (temperature) is 16 bits wide with scale 2
+        let descriptors = [
+            desc(2, 1, 130),
+            desc(0, 12, 101),
+            desc(2, 1, 0),
+            desc(0, 12, 101),
+        ];
+        let events =
+            read_all_events(&descriptors, 1, false, &[(18, 100_000), (16, 29315)]).unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(100_000, -2), Value::Decimal(29315, -2)]
+        );
+
+        // Compressed: local reference, 6-bit NBINC, then one increment per subset
+        let events = read_all_events(
+            &descriptors,
+            2,
+            true,
+            &[(18, 100_000), (6, 2), (2, 0), (2, 1), (16, 29315), (6, 0)],
+        )
+        .unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![
+                Value::Decimal(100_000, -2),
+                Value::Decimal(100_001, -2),
+                Value::Decimal(29315, -2),
+                Value::Decimal(29315, -2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_change_reference_values() {
+        // 0-07-002 (height) is 16 bits wide with scale -1 and reference -40
+        let descriptors = [
+            desc(2, 3, 12),
+            desc(0, 7, 2),
+            desc(2, 3, 255),
+            desc(0, 7, 2),
+            desc(2, 3, 0),
+            desc(0, 7, 2),
+        ];
+        let events = read_all_events(
+            &descriptors,
+            1,
+            false,
+            &[(12, 0b1000_0000_0101), (16, 10), (16, 10)],
+        )
+        .unwrap();
+        assert!(events.iter().any(|ev| matches!(
+            ev,
+            DataEvent::ReferenceValueDefined {
+                idx: 1,
+                reference_value: -5,
+                ..
+            }
+        )));
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(5, 1), Value::Decimal(-30, 1)]
+        );
+
+        // Compressed: the new reference value is followed by a zero NBINC
+        let events = read_all_events(
+            &descriptors[..4],
+            2,
+            true,
+            &[(12, 100), (6, 0), (16, 10), (6, 0)],
+        )
+        .unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(110, 1), Value::Decimal(110, 1)]
+        );
+    }
+
+    #[test]
+    fn test_associated_fields() {
+        let descriptors = [
+            desc(2, 4, 8),
+            desc(0, 31, 21),
+            desc(0, 12, 101),
+            desc(2, 4, 2),
+            desc(0, 31, 21),
+            desc(0, 12, 101),
+            desc(2, 4, 0),
+            desc(0, 12, 101),
+            desc(2, 4, 0),
+            desc(0, 12, 101),
+        ];
+        let fields = [
+            (6, 2),
+            (8, 95),
+            (16, 29315),
+            (6, 1),
+            (8, 90),
+            (2, 1),
+            (16, 29316),
+            (8, 85),
+            (16, 29317),
+            (16, 29318),
+        ];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        let associated: Vec<_> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                DataEvent::AssociatedField {
+                    idx,
+                    significance,
+                    bits,
+                    value,
+                } => Some((*idx, *significance, *bits, *value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            associated,
+            vec![
+                (2, Some(2), 8, 95),
+                (5, Some(2), 8, 90),
+                (5, Some(1), 2, 1),
+                (7, Some(2), 8, 85),
+            ]
+        );
+        assert_eq!(
+            data_values(&events),
+            vec![
+                Value::Integer(2),
+                Value::Decimal(29315, -2),
+                Value::Integer(1),
+                Value::Decimal(29316, -2),
+                Value::Decimal(29317, -2),
+                Value::Decimal(29318, -2),
+            ]
+        );
+        // The associated field is reported right before its data element
+        let pos = events
+            .iter()
+            .position(|ev| matches!(ev, DataEvent::AssociatedField { idx: 2, .. }))
+            .unwrap();
+        assert!(matches!(events[pos + 1], DataEvent::Data { idx: 2, .. }));
+
+        // Compressed
+        let events = read_all_events(
+            &descriptors[..3],
+            2,
+            true,
+            &[
+                (6, 2),
+                (6, 0),
+                (8, 90),
+                (6, 4),
+                (4, 5),
+                (4, 0),
+                (16, 29315),
+                (6, 0),
+            ],
+        )
+        .unwrap();
+        assert!(events.iter().any(|ev| matches!(
+            ev,
+            DataEvent::CompressedAssociatedField {
+                idx: 2,
+                significance: Some(2),
+                bits: 8,
+                values,
+            } if values == &vec![95, 90]
+        )));
+    }
+
+    #[test]
+    fn test_signify_character() {
+        let descriptors = [desc(2, 5, 3), desc(0, 12, 101)];
+        let fields = [
+            (8, b'A' as u64),
+            (8, b'B' as u64),
+            (8, b'C' as u64),
+            (16, 29315),
+        ];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        assert!(matches!(
+            &events[1],
+            DataEvent::Characters { idx: 0, value } if value == "ABC"
+        ));
+        assert_eq!(data_values(&events), vec![Value::Decimal(29315, -2)]);
+    }
+
+    #[test]
+    fn test_change_character_width() {
+        // 0-01-015 (station or site name) is 160 bits wide
+        let descriptors = [
+            desc(2, 8, 2),
+            desc(0, 1, 15),
+            desc(2, 8, 0),
+            desc(0, 12, 101),
+        ];
+        let fields = [(8, b'O' as u64), (8, b'K' as u64), (16, 29315)];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![Value::String("OK".to_string()), Value::Decimal(29315, -2)]
+        );
+    }
+
+    #[test]
+    fn test_skip_local_descriptor() {
+        let descriptors = [
+            desc(2, 6, 10),
+            desc(0, 1, 192),
+            desc(0, 12, 101),
+            // Known descriptor with the declared width
+            desc(2, 6, 16),
+            desc(0, 12, 101),
+        ];
+        let events = read_all_events(
+            &descriptors,
+            1,
+            false,
+            &[(10, 1023), (16, 29315), (16, 29316)],
+        )
+        .unwrap();
+        assert!(matches!(
+            events[2],
+            DataEvent::SkippedData {
+                idx: 1,
+                xy: XY { x: 1, y: 192 },
+                bits: 10
+            }
+        ));
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(29315, -2), Value::Decimal(29316, -2)]
+        );
+
+        // Compressed
+        let events = read_all_events(
+            &descriptors[..3],
+            2,
+            true,
+            &[(10, 0), (6, 3), (3, 1), (3, 2), (16, 29315), (6, 0)],
+        )
+        .unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(29315, -2), Value::Decimal(29315, -2)]
+        );
+    }
+
+    #[test]
+    fn test_increase_precision() {
+        // 0-07-002 (height): 16 bits, scale -1, reference -40
+        // With 2-07-002: 23 bits, scale 1, reference -4000
+        let descriptors = [
+            desc(2, 7, 2),
+            desc(0, 7, 2),
+            desc(2, 2, 129),
+            desc(0, 7, 2),
+            desc(2, 2, 0),
+            desc(2, 7, 0),
+            desc(0, 7, 2),
+        ];
+        let events = read_all_events(
+            &descriptors,
+            1,
+            false,
+            &[(23, 12345), (23, 12345), (16, 100)],
+        )
+        .unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![
+                Value::Decimal(8345, -1),
+                Value::Decimal(8345, -2),
+                Value::Decimal(60, 1),
+            ]
+        );
+
+        // Compressed
+        let events = read_all_events(
+            &descriptors[..2],
+            2,
+            true,
+            &[(23, 4000), (6, 2), (2, 0), (2, 1)],
+        )
+        .unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(0, -1), Value::Decimal(1, -1)]
+        );
+    }
+
+    #[test]
+    fn test_bitmap_back_references() {
+        let descriptors = [
+            desc(0, 12, 101),
+            desc(0, 12, 103),
+            // Quality information with a bitmap defined for reuse
+            desc(2, 22, 0),
+            desc(2, 36, 0),
+            desc(1, 1, 2),
+            desc(0, 31, 31),
+            desc(0, 33, 7),
+            // Substituted value
+            desc(2, 23, 0),
+            desc(2, 37, 0),
+            desc(2, 23, 255),
+            // Difference statistical value
+            desc(2, 25, 0),
+            desc(2, 37, 0),
+            desc(2, 25, 255),
+            desc(2, 35, 0),
+        ];
+        let fields = [
+            (16, 29315),
+            (16, 28000),
+            (1, 0),
+            (1, 1),
+            (7, 70),
+            (16, 29400),
+            (17, 65536 + 5),
+        ];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        let referenced: Vec<_> = events
+            .windows(2)
+            .filter_map(|pair| match pair {
+                [
+                    DataEvent::BackReference {
+                        kind,
+                        position,
+                        xy: target,
+                    },
+                    DataEvent::Data { xy, value, .. },
+                ] => Some((*kind, *position, *target, *xy, value.clone())),
+                _ => None,
+            })
+            .collect();
+        let temperature = XY { x: 12, y: 101 };
+        assert_eq!(
+            referenced,
+            vec![
+                (
+                    BackReferenceKind::QualityInformation,
+                    0,
+                    temperature,
+                    XY { x: 33, y: 7 },
+                    Value::Integer(70)
+                ),
+                (
+                    BackReferenceKind::SubstitutedValue,
+                    0,
+                    temperature,
+                    temperature,
+                    Value::Decimal(29400, -2)
+                ),
+                (
+                    BackReferenceKind::DifferenceStatistics,
+                    0,
+                    temperature,
+                    temperature,
+                    Value::Decimal(5, -2)
+                ),
+            ]
+        );
+
+        // A marker without a referenced element left is an error
+        let descriptors = [
+            desc(0, 12, 101),
+            desc(2, 23, 0),
+            desc(0, 31, 31),
+            desc(2, 23, 255),
+            desc(2, 23, 255),
+        ];
+        let result = read_all_events(&descriptors, 1, false, &[(16, 0), (1, 0), (16, 0)]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_define_event() {
+        let descriptors = [
+            desc(0, 12, 101),
+            desc(0, 12, 103),
+            desc(2, 41, 0),
+            desc(1, 1, 2),
+            desc(0, 31, 31),
+            desc(0, 8, 2),
+            desc(2, 41, 255),
+            desc(0, 8, 2),
+        ];
+        let fields = [(16, 29315), (16, 28000), (1, 0), (1, 0), (6, 3), (6, 4)];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        let references: Vec<_> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                DataEvent::BackReference { kind, position, xy } => Some((*kind, *position, *xy)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            references,
+            // The second element is not referred to after 2-41-255
+            vec![(BackReferenceKind::Event, 0, XY { x: 12, y: 101 })]
+        );
+    }
+
+    #[test]
+    fn test_operator_handler() {
+        /// Local operator 2-60-YYY: YYY bits of flags, then change the scale by the flags
+        struct LocalOperator;
+
+        impl OperatorHandler for LocalOperator {
+            fn handle(
+                &mut self,
+                ctx: &mut OperatorContext<'_>,
+                idx: u16,
+                xy: XY,
+            ) -> Result<Option<DataEvent>, Error> {
+                let flags = ctx.read_bits(xy.y as u32)?;
+                ctx.modifiers.scale_offset = flags as i8;
+                Ok(Some(DataEvent::Custom {
+                    idx,
+                    xy,
+                    values: vec![Value::Integer(flags as i64)],
+                }))
+            }
+        }
+
+        let tables = Tables::default();
+        let descriptors = [desc(2, 60, 4), desc(0, 12, 101)];
+        let events = read_all_events_with_tables(
+            &tables,
+            &descriptors,
+            1,
+            false,
+            &[(4, 1), (16, 29315)],
+            |reader| reader.register_operator_handler(60, LocalOperator),
+        )
+        .unwrap();
+        assert!(matches!(
+            &events[1],
+            DataEvent::Custom { idx: 0, xy: XY { x: 60, y: 4 }, values }
+                if values == &vec![Value::Integer(1)]
+        ));
+        assert_eq!(data_values(&events), vec![Value::Decimal(29315, -3)]);
+    }
+
+    #[test]
+    fn test_data_not_present() {
+        let descriptors = [
+            desc(2, 21, 3),
+            desc(0, 12, 101),
+            desc(0, 4, 4),
+            desc(0, 12, 103),
+            desc(0, 12, 101),
+        ];
+        let events = read_all_events(&descriptors, 1, false, &[(5, 12), (16, 29315)]).unwrap();
+        let not_present: Vec<_> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                DataEvent::NotPresent { idx, xy } => Some((*idx, *xy)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            not_present,
+            vec![(1, XY { x: 12, y: 101 }), (3, XY { x: 12, y: 103 })]
+        );
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Integer(12), Value::Decimal(29315, -2)]
+        );
+    }
+
+    #[test]
+    fn test_compressed_characters() {
+        fn chars(s: &[u8]) -> Vec<(u32, u64)> {
+            s.iter().map(|&c| (8, c as u64)).collect()
+        }
+
+        // 0-01-008 (aircraft registration number) is 64 bits wide
+        let descriptors = [desc(0, 1, 8), desc(0, 1, 8), desc(0, 1, 8), desc(2, 5, 2)];
+        let mut fields = vec![];
+        // Different strings in each subset
+        fields.extend(chars(&[0; 8]));
+        fields.push((6, 8));
+        fields.extend(chars(b"JA01AB  "));
+        fields.extend(chars(&[0xff; 8]));
+        fields.extend(chars(b"JA02CD  "));
+        // Identical strings
+        fields.extend(chars(b"JA03EF  "));
+        fields.push((6, 0));
+        // All missing
+        fields.extend(chars(&[0xff; 8]));
+        fields.push((6, 0));
+        // 2-05-002
+        fields.extend(chars(b"OK"));
+        fields.push((6, 0));
+
+        let events = read_all_events(&descriptors, 3, true, &fields).unwrap();
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(
+            data_values(&events),
+            vec![
+                string("JA01AB  "),
+                Value::Missing,
+                string("JA02CD  "),
+                string("JA03EF  "),
+                string("JA03EF  "),
+                string("JA03EF  "),
+                Value::Missing,
+                Value::Missing,
+                Value::Missing,
+            ]
+        );
+        assert!(matches!(
+            &events[4],
+            DataEvent::CompressedCharacters { idx: 3, values }
+                if values == &vec![string("OK"), string("OK"), string("OK")]
+        ));
+    }
+
+    #[test]
+    fn test_compressed_delayed_replication() {
+        // 1-01-000 031001 0-12-101
+        let descriptors = [desc(1, 1, 0), desc(0, 31, 1), desc(0, 12, 101)];
+        let fields = [
+            // Factor 2 in both subsets, encoded as reference 2 and NBINC 0
+            (8, 2),
+            (6, 0),
+            (16, 27315),
+            (6, 2),
+            (2, 0),
+            (2, 1),
+            (16, 27415),
+            (6, 0),
+        ];
+        let events = read_all_events(&descriptors, 2, true, &fields).unwrap();
+        assert!(matches!(
+            events[1],
+            DataEvent::ReplicationStart { idx: 0, count: 2 }
+        ));
+        assert_eq!(
+            data_values(&events),
+            vec![
+                Value::Decimal(27315, -2),
+                Value::Decimal(27316, -2),
+                Value::Decimal(27415, -2),
+                Value::Decimal(27415, -2),
+            ]
+        );
+
+        // The factor must be identical across subsets
+        let fields = [(8, 1), (6, 1), (1, 0), (1, 1)];
+        assert!(matches!(
+            read_all_events(&descriptors, 2, true, &fields),
+            Err(Error::Fatal(_))
+        ));
+    }
+
+    #[test]
+    fn test_delayed_repetition() {
+        // 1-02-000 031011 (1-01-002 0-12-101) 0-12-103: the block is stored only once
+        let descriptors = [
+            desc(1, 2, 0),
+            desc(0, 31, 11),
+            desc(1, 1, 2),
+            desc(0, 12, 101),
+            desc(0, 12, 103),
+        ];
+        let fields = [(8, 3), (16, 27315), (16, 27316), (16, 27015)];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        let mut expected = vec![];
+        for _ in 0..3 {
+            expected.extend([Value::Decimal(27315, -2), Value::Decimal(27316, -2)]);
+        }
+        expected.push(Value::Decimal(27015, -2));
+        assert_eq!(data_values(&events), expected);
+        let count = |f: fn(&DataEvent) -> bool| events.iter().filter(|ev| f(ev)).count();
+        assert_eq!(
+            count(|ev| matches!(ev, DataEvent::ReplicationStart { .. })),
+            4
+        );
+        assert_eq!(count(|ev| matches!(ev, DataEvent::ReplicationItemStart)), 9);
+        assert_eq!(count(|ev| matches!(ev, DataEvent::ReplicationItemEnd)), 9);
+        assert_eq!(count(|ev| matches!(ev, DataEvent::ReplicationEnd)), 4);
+    }
+
+    #[test]
+    fn test_delayed_repetition_back_references() {
+        // 1-02-000 031011 (0-12-101 0-12-103), then quality information of the last element
+        let descriptors = [
+            desc(1, 2, 0),
+            desc(0, 31, 11),
+            desc(0, 12, 101),
+            desc(0, 12, 103),
+            desc(2, 22, 0),
+            desc(1, 1, 6),
+            desc(0, 31, 31),
+            desc(0, 33, 7),
+        ];
+        let mut fields = vec![(8, 3), (16, 27315), (16, 27015)];
+        fields.extend([(1, 1), (1, 1), (1, 1), (1, 1), (1, 1), (1, 0), (7, 70)]);
+        let tables = Tables::default();
+        let events = read_all_events_with_tables(&tables, &descriptors, 1, false, &fields, |r| {
+            r.set_verbose(true)
+        })
+        .unwrap();
+        let references: Vec<_> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                DataEvent::BackReference { position, xy, .. } => Some((*position, *xy)),
+                _ => None,
+            })
+            .collect();
+        // The replication factor is the first element of the backward reference list
+        assert_eq!(references, vec![(6, XY { x: 12, y: 103 })]);
+
+        // Element layouts are not repeated, as they are not read again
+        let layouts: Vec<_> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                DataEvent::ElementLayout { xy, bit_offset, .. } => Some((*xy, *bit_offset)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(layouts.len(), 2 + 6 + 1);
+        assert_eq!(layouts[0], (XY { x: 12, y: 101 }, 40));
+        assert_eq!(layouts[1], (XY { x: 12, y: 103 }, 56));
+        assert_eq!(layouts[2], (XY { x: 31, y: 31 }, 72));
+    }
+
+    #[test]
+    fn test_wide_numeric_elements() {
+        // 2-01-152 widens 0-12-101 to 40 bits, 2-01-176 to 64 bits
+        let descriptors = [
+            desc(2, 1, 152),
+            desc(0, 12, 101),
+            desc(2, 1, 176),
+            desc(0, 12, 101),
+            desc(0, 12, 101),
+        ];
+        let fields = [(40, 1 << 35), (64, 1 << 62), (64, u64::MAX)];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![
+                Value::Decimal(1 << 35, -2),
+                Value::Decimal(1 << 62, -2),
+                Value::Missing,
+            ]
+        );
+
+        // Compressed data with a 40-bit local reference and 33-bit increments
+        let descriptors = [desc(2, 1, 152), desc(0, 12, 101)];
+        let fields = [(40, 1 << 36), (6, 33), (33, 0), (33, 1 << 32)];
+        let events = read_all_events(&descriptors, 2, true, &fields).unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![
+                Value::Decimal(1 << 36, -2),
+                Value::Decimal((1 << 36) + (1 << 32), -2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_large_reference_values() {
+        use crate::tables::local::jma::JMA_DATA_DESCRIPTORS;
+
+        let mut tables = Tables::default();
+        for b in JMA_DATA_DESCRIPTORS.iter() {
+            tables.table_b.insert(b.xy, b);
+        }
+        // JMA river number (0-01-210) has a reference value of 80000000 with 24 bits
+        let descriptors = [desc(0, 1, 210)];
+        let fields = [(24, (1 << 24) - 2)];
+        let events =
+            read_all_events_with_tables(&tables, &descriptors, 1, false, &fields, |_| {}).unwrap();
+        assert_eq!(data_values(&events), vec![Value::Integer(96777214)]);
+
+        // 2-07-009 multiplies the reference value by 10^9
+        let descriptors = [desc(2, 7, 9), desc(0, 1, 210)];
+        let fields = [(54, (1 << 53) + 5)];
+        let events =
+            read_all_events_with_tables(&tables, &descriptors, 1, false, &fields, |_| {}).unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(80_000_000_000_000_000 + (1 << 53) + 5, -9)]
+        );
+
+        // ... but 10^12 makes it overflow
+        let descriptors = [desc(2, 7, 12), desc(0, 1, 210)];
+        let fields = [(64, 0)];
+        assert!(matches!(
+            read_all_events_with_tables(&tables, &descriptors, 1, false, &fields, |_| {}),
+            Err(Error::OutOfRange(_))
+        ));
+
+        // A zero reference value does not overflow with 2-07-019 (10^19)
+        let descriptors = [desc(2, 1, 112), desc(2, 7, 19), desc(0, 12, 101)];
+        let events = read_all_events(&descriptors, 1, false, &[(64, 12345)]).unwrap();
+        assert_eq!(data_values(&events), vec![Value::Decimal(12345, -21)]);
+
+        // Raw values beyond i64 are rejected
+        let descriptors = [desc(2, 1, 176), desc(0, 12, 101)];
+        assert!(matches!(
+            read_all_events(&descriptors, 1, false, &[(64, 1 << 63)]),
+            Err(Error::OutOfRange(_))
+        ));
+
+        // Negative reference values are added without wrapping
+        let descriptors = [desc(2, 1, 176), desc(0, 7, 2)];
+        let events = read_all_events(&descriptors, 1, false, &[(64, 0)]).unwrap();
+        assert_eq!(data_values(&events), vec![Value::Decimal(-40, 1)]);
+
+        // Compressed increments must not overflow the local reference value
+        let descriptors = [desc(2, 1, 176), desc(0, 12, 101)];
+        let fields = [(64, u64::MAX - 1), (6, 2), (2, 0), (2, 2)];
+        assert!(matches!(
+            read_all_events(&descriptors, 2, true, &fields),
+            Err(Error::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_typed_values() {
+        assert_eq!(Value::Decimal(27315, -2).as_f64(), Some(273.15));
+        assert_eq!(Value::Decimal(27315, -2).as_i64(), None);
+        assert_eq!(Value::Decimal(27300, -2).as_i64(), Some(273));
+        assert_eq!(Value::Decimal(12, 3).as_i64(), Some(12000));
+        assert_eq!(Value::Decimal(12, 3).as_f64(), Some(12000.0));
+        assert_eq!(Value::Decimal(12, 3).as_decimal(), Some((12, 3)));
+        assert_eq!(Value::Integer(-40).as_decimal(), Some((-40, 0)));
+        assert_eq!(Value::Integer(-40).as_f64(), Some(-40.0));
+        assert_eq!(Value::Missing.as_f64(), None);
+        assert!(Value::Missing.is_missing());
+        assert_eq!(Value::String("ABC".to_string()).as_str(), Some("ABC"));
+        assert_eq!(Value::String("ABC".to_string()).as_i64(), None);
+        assert_eq!(
+            Value::Decimal(27315, -2).convert_unit("K", "C").unwrap(),
+            Some(0.0)
+        );
+        assert_eq!(Value::Missing.convert_unit("K", "C").unwrap(), None);
+        assert!(matches!(
+            Value::Integer(1).convert_unit("K", "hPa"),
+            Err(Error::UnitConversion(message)) if message.contains("\"K\" to \"hPa\"")
+        ));
+
+        // The unit and the scale in effect are attached to data events
+        let descriptors = [desc(2, 2, 129), desc(0, 12, 101)];
+        let events = read_all_events(&descriptors, 1, false, &[(16, 27315)]).unwrap();
+        assert!(matches!(
+            events
+                .iter()
+                .find(|ev| matches!(ev, DataEvent::Data { .. })),
+            Some(DataEvent::Data {
+                unit: "K",
+                scale: 3,
+                value: Value::Decimal(27315, -3),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_missing_rule() {
+        // 0-31-001 (8 bits), 0-31-031 (1 bit), 0-08-042 (18-bit flag table) twice,
+        // and 0-04-004 narrowed to 1 bit by 2-01-124
+        let descriptors = [
+            desc(0, 31, 1),
+            desc(0, 31, 31),
+            desc(0, 8, 42),
+            desc(0, 8, 42),
+            desc(2, 1, 124),
+            desc(0, 4, 4),
+        ];
+        let fields = [(8, 255), (1, 1), (18, (1 << 18) - 1), (18, 1), (1, 1)];
+        let events = read_all_events(&descriptors, 1, false, &fields).unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![
+                Value::Integer(255),
+                Value::Integer(1),
+                Value::Missing,
+                Value::Integer(1),
+                Value::Integer(1),
+            ]
+        );
+        // The raw bits are kept for missing values as well
+        let raws: Vec<_> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                DataEvent::Data { raw, .. } => *raw,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(raws, vec![255, 1, (1 << 18) - 1, 1, 1]);
+
+        // Compressed: increments with all bits set mean missing values
+        let descriptors = [desc(0, 12, 101)];
+        let fields = [(16, 27315), (6, 2), (2, 1), (2, 3)];
+        let events = read_all_events(&descriptors, 2, true, &fields).unwrap();
+        assert_eq!(
+            data_values(&events),
+            vec![Value::Decimal(27316, -2), Value::Missing]
+        );
+        assert!(events.iter().any(|ev| matches!(
+            ev,
+            DataEvent::CompressedData { raw_values: Some(raw_values), .. }
+                if raw_values == &vec![27316, 65535]
+        )));
+    }
+
+    #[test]
+    fn test_verbose_element_layout() {
+        fn layouts(
+            compressed: bool,
+            descriptors: &[Descriptor],
+            fields: &[(u32, u64)],
+        ) -> Vec<(u64, u32, i8, i64)> {
+            let tables = Tables::default();
+            let events = read_all_events_with_tables(
+                &tables,
+                descriptors,
+                2,
+                compressed,
+                fields,
+                |reader| reader.set_verbose(true),
+            )
+            .unwrap();
+            events
+                .into_iter()
+                .filter_map(|ev| match ev {
+                    DataEvent::ElementLayout {
+                        bit_offset,
+                        bit_width,
+                        scale,
+                        reference_value,
+                        ..
+                    } => Some((bit_offset, bit_width, scale, reference_value)),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        // 0-07-002 (16 bits, scale -1, reference -40) and 0-04-004 (5 bits)
+        let descriptors = [desc(0, 7, 2), desc(0, 4, 4)];
+        assert_eq!(
+            layouts(false, &descriptors, &[(16, 100), (5, 3), (16, 200), (5, 4)]),
+            vec![
+                (32, 16, -1, -40),
+                (48, 5, 0, 0),
+                (53, 16, -1, -40),
+                (69, 5, 0, 0)
+            ]
+        );
+        // Compressed data (offsets of the local reference values)
+        assert_eq!(
+            layouts(
+                true,
+                &descriptors,
+                &[(16, 100), (6, 1), (1, 0), (1, 1), (5, 3), (6, 0)]
+            ),
+            vec![(32, 16, -1, -40), (56, 5, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_strict_section_length() {
+        fn read_strict(section: &[u8], number_of_subsets: u16, edition: u8) -> Result<u64, Error> {
+            let tables = Tables::default();
+            // 0-01-001 (7 bits)
+            let descriptors = [desc(0, 1, 1)];
+            let mut cursor = Cursor::new(section.to_vec());
+            read_section_events(
+                &tables,
+                &descriptors,
+                number_of_subsets,
+                false,
+                &mut cursor,
+                |reader| reader.set_strict(Some(edition)),
+            )?;
+            Ok(cursor.position())
+        }
+
+        // Padding in the last octet is skipped, and a whole padding octet until edition 3
+        let section = make_data_section(&[(7, 1), (7, 2)]);
+        assert_eq!(read_strict(&section, 2, 4).unwrap(), 6);
+        let section = make_data_section(&[(7, 1), (7, 2), (8, 0)]);
+        assert_eq!(read_strict(&section, 2, 3).unwrap(), 7);
+        assert!(matches!(
+            read_strict(&section, 2, 4),
+            Err(Error::Length(LengthError::DataSectionUnderrun {
+                section_length: 7,
+                bits_read: 46
+            }))
+        ));
+
+        // Data left in the section
+        let section = make_data_section(&[(7, 1), (7, 2), (16, 0)]);
+        assert!(matches!(
+            read_strict(&section, 2, 3),
+            Err(Error::Length(LengthError::DataSectionUnderrun {
+                section_length: 8,
+                bits_read: 46
+            }))
+        ));
+
+        // Data beyond the section length
+        let mut section = make_data_section(&[(7, 1), (7, 2), (7, 3), (7, 4)]);
+        section[2] = 5;
+        assert!(matches!(
+            read_strict(&section, 4, 4),
+            Err(Error::Length(LengthError::DataSectionOverrun {
+                section_length: 5,
+                bits_read: 46
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_change_data_width_ignores_code_tables() {
+        // 0-08-002 (vertical significance) is a 6-bit code table
+        let descriptors = [desc(2, 1, 130), desc(0, 8, 2)];
+        let events = read_all_events(&descriptors, 1, false, &[(6, 5)]).unwrap();
+        assert_eq!(data_values(&events), vec![Value::Integer(5)]);
+    }
 
     #[test]
     fn test_value_fmt() {
@@ -0,0 +1,264 @@
+//! Finding BUFR messages in a stream with other data (e.g. GTS headings) between them
+
+use std::io::Read;
+
+use crate::Error;
+
+const START_MAGIC: &[u8; 4] = b"BUFR";
+const END_MAGIC: &[u8; 4] = b"7777";
+const CHUNK_SIZE: usize = 8192;
+
+/// A BUFR message found by [`MessageScanner`]
+#[derive(Debug, Clone)]
+pub struct FramedMessage {
+    /// Byte offset of "BUFR" in the stream
+    pub offset: u64,
+    /// The whole message from "BUFR" to "7777"
+    pub data: Vec<u8>,
+}
+
+impl FramedMessage {
+    pub fn edition_number(&self) -> u8 {
+        self.data[7]
+    }
+}
+
+/// Iterator over the BUFR messages in a stream
+///
+/// Each message is framed by `total_length` of its indicator section (by the sum of the section
+/// lengths for editions 0 and 1) and must end with "7777".
+/// Corrupt messages are yielded as errors, and scanning resumes right after their "BUFR".
+pub struct MessageScanner<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// Stream offset of `buf[0]`
+    offset: u64,
+    eof: bool,
+}
+
+impl<R: Read> MessageScanner<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// Read until the buffer has at least `len` bytes or the stream ends
+    fn fill(&mut self, len: usize) -> Result<(), Error> {
+        while self.buf.len() < len && !self.eof {
+            let start = self.buf.len();
+            self.buf.resize(start + CHUNK_SIZE.max(len - start), 0);
+            let result = self.reader.read(&mut self.buf[start..]);
+            let n = *result.as_ref().unwrap_or(&0);
+            self.buf.truncate(start + n);
+            if let Err(e) = result {
+                if e.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                // Stop reading, but the buffered data can still be scanned
+                self.eof = true;
+                return Err(e.into());
+            }
+            if n == 0 {
+                self.eof = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.buf.drain(..len);
+        self.offset += len as u64;
+    }
+
+    fn next_message(&mut self) -> Result<Option<FramedMessage>, Error> {
+        // Look for "BUFR"
+        loop {
+            if let Some(pos) = self
+                .buf
+                .windows(START_MAGIC.len())
+                .position(|w| w == START_MAGIC)
+            {
+                self.consume(pos);
+                break;
+            }
+            if self.eof {
+                self.consume(self.buf.len());
+                return Ok(None);
+            }
+            // Keep the bytes that may be the beginning of "BUFR"
+            self.consume(self.buf.len().saturating_sub(START_MAGIC.len() - 1));
+            self.fill(self.buf.len() + 1)?;
+        }
+
+        let result = self.frame();
+        if result.is_err() {
+            // Skip the magic of the corrupt message
+            self.consume(START_MAGIC.len());
+        }
+        result.map(Some)
+    }
+
+    /// Frame the message at the beginning of the buffer
+    fn frame(&mut self) -> Result<FramedMessage, Error> {
+        let offset = self.offset;
+        self.fill(8)?;
+        if self.buf.len() < 8 {
+            return Err(Error::Fatal(format!(
+                "Truncated BUFR message at offset {}",
+                offset
+            )));
+        }
+        let total_length = match self.buf[7] {
+            // Editions 0 and 1 have no total length
+            0 | 1 => self.sum_section_lengths()?,
+            _ => u32::from_be_bytes([0, self.buf[4], self.buf[5], self.buf[6]]) as usize,
+        };
+        if total_length < 8 + END_MAGIC.len() {
+            return Err(Error::Fatal(format!(
+                "Invalid total length {} of BUFR message at offset {}",
+                total_length, offset
+            )));
+        }
+        self.fill(total_length)?;
+        if self.buf.len() < total_length {
+            return Err(Error::Fatal(format!(
+                "Truncated BUFR message at offset {} ({} of {} bytes)",
+                offset,
+                self.buf.len(),
+                total_length
+            )));
+        }
+        if &self.buf[total_length - END_MAGIC.len()..total_length] != END_MAGIC {
+            return Err(Error::Fatal(format!(
+                "End section not found at the end of BUFR message at offset {}",
+                offset
+            )));
+        }
+        let data = self.buf[..total_length].to_vec();
+        self.consume(total_length);
+        Ok(FramedMessage { offset, data })
+    }
+
+    /// Length of the message at the beginning of the buffer from its section lengths
+    fn sum_section_lengths(&mut self) -> Result<usize, Error> {
+        let offset = self.offset;
+        let mut end = START_MAGIC.len();
+        let mut has_optional_section = false;
+        for section in 1..=4 {
+            if section == 2 && !has_optional_section {
+                continue;
+            }
+            // The flags are in octet 8 of Section 1
+            let header_length = if section == 1 { 8 } else { 3 };
+            self.fill(end + header_length)?;
+            if self.buf.len() < end + header_length {
+                return Err(Error::Fatal(format!(
+                    "Truncated BUFR message at offset {}",
+                    offset
+                )));
+            }
+            let length =
+                u32::from_be_bytes([0, self.buf[end], self.buf[end + 1], self.buf[end + 2]]);
+            if length < 4 {
+                return Err(Error::Fatal(format!(
+                    "Invalid length {} of section {} of BUFR message at offset {}",
+                    length, section, offset
+                )));
+            }
+            if section == 1 {
+                has_optional_section = self.buf[end + 7] & 0b10000000 != 0;
+            }
+            end += length as usize;
+        }
+        Ok(end + END_MAGIC.len())
+    }
+}
+
+impl<R: Read> Iterator for MessageScanner<R> {
+    type Item = Result<FramedMessage, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_message(body: &[u8]) -> Vec<u8> {
+        let total_length = (8 + body.len() + 4) as u32;
+        let mut message = b"BUFR".to_vec();
+        message.extend(&total_length.to_be_bytes()[1..]);
+        message.push(4);
+        message.extend(body);
+        message.extend(b"7777");
+        message
+    }
+
+    #[test]
+    fn test_scan_messages() {
+        let first = make_message(&[1; 20]);
+        let second = make_message(&[2; CHUNK_SIZE * 2]);
+        let mut corrupt = make_message(&[3; 10]);
+        corrupt.truncate(corrupt.len() - 1);
+
+        let mut stream = b"ISTC62 RJTD 310000\n".to_vec();
+        stream.extend(&first);
+        stream.extend(b"\r\r\n junk BUF");
+        stream.extend(&corrupt);
+        stream.extend(b"\x03");
+        let second_offset = stream.len() as u64;
+        stream.extend(&second);
+        stream.extend(b"BUFR\x00");
+
+        let results: Vec<_> = MessageScanner::new(&stream[..]).collect();
+        assert_eq!(results.len(), 4);
+        let message = results[0].as_ref().unwrap();
+        assert_eq!(message.offset, 19);
+        assert_eq!(message.data, first);
+        assert_eq!(message.edition_number(), 4);
+        assert!(matches!(results[1], Err(Error::Fatal(_))));
+        let message = results[2].as_ref().unwrap();
+        assert_eq!(message.offset, second_offset);
+        assert_eq!(message.data, second);
+        // Truncated message at the end
+        assert!(results[3].is_err());
+
+        assert_eq!(MessageScanner::new(&b"no messages"[..]).count(), 0);
+    }
+
+    #[test]
+    fn test_scan_edition_1() {
+        // Sections 1 to 4, with an optional section in the second message
+        let mut first = b"BUFR".to_vec();
+        first.extend([0, 0, 18, 1, 0, 34, 0, 0, 0, 1, 2, 0, 99, 12, 31, 23, 59, 0]);
+        first.extend([0, 0, 10, 0, 0, 1, 0x80, 0x01, 0x01, 0]);
+        first.extend([0, 0, 6, 0, 44 << 1, 0]);
+        first.extend(b"7777");
+        let mut second = first.clone();
+        second[11] = 0x80;
+        second.splice(22..22, [0, 0, 6, 0, 0xab, 0xcd]);
+
+        let mut stream = b"IUSN01 RJTD 010600\n".to_vec();
+        stream.extend(&first);
+        stream.extend(&second);
+        let messages: Vec<_> = MessageScanner::new(&stream[..])
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].offset, 19);
+        assert_eq!(messages[0].data, first);
+        assert_eq!(messages[0].edition_number(), 1);
+        assert_eq!(messages[1].data, second);
+
+        // Section lengths not ending with "7777"
+        let mut corrupt = first.clone();
+        corrupt[24] = 12;
+        assert!(MessageScanner::new(&corrupt[..]).next().unwrap().is_err());
+    }
+}
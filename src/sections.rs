@@ -1,5 +1,7 @@
 //! The header sections of a BUFR file
 
+use std::io::SeekFrom;
+
 use binrw::{BinRead, BinReaderExt};
 use serde::Serialize;
 
@@ -18,12 +20,31 @@ pub struct HeaderSections {
 impl HeaderSections {
     pub fn read<R: BinReaderExt>(mut reader: R) -> Result<Self, Error> {
         // Indicator section
-        let indicator_section: IndicatorSection = reader.read_be()?;
+        let mut indicator_section: IndicatorSection = reader.read_be()?;
 
         // Identification section
         let identification_section: IdentificationSection = match indicator_section.edition_number {
-            3 => reader.read_be::<IdentificationSectionV3>()?.into(),
-            4 => reader.read_be::<IdentificationSection>()?,
+            0 | 1 => {
+                // Section 0 is only "BUFR", and the octets read as the total length and the
+                // edition number are the first four octets of Section 1.
+                let section_length = std::mem::take(&mut indicator_section.total_length);
+                ensure_section_length(1, section_length, 17)?;
+                reader
+                    .read_be_args::<IdentificationSectionV1>((section_length,))?
+                    .into()
+            }
+            2 => {
+                check_section_length(&mut reader, 1, 17)?;
+                reader.read_be::<IdentificationSectionV2>()?.into()
+            }
+            3 => {
+                check_section_length(&mut reader, 1, 17)?;
+                reader.read_be::<IdentificationSectionV3>()?.into()
+            }
+            4 => {
+                check_section_length(&mut reader, 1, 22)?;
+                reader.read_be::<IdentificationSection>()?
+            }
             _ => {
                 return Err(Error::Fatal(format!(
                     "Unsupported edition number {}",
@@ -35,11 +56,15 @@ impl HeaderSections {
         // Optional section
         let optional_section: Option<OptionalSection> =
             match identification_section.flags.has_optional_section {
-                true => Some(reader.read_be()?),
+                true => {
+                    check_section_length(&mut reader, 2, 4)?;
+                    Some(reader.read_be()?)
+                }
                 false => None,
             };
 
         // Data description section
+        check_section_length(&mut reader, 3, 7)?;
         let data_description_section: DataDescriptionSection = reader.read_be()?;
 
         Ok(HeaderSections {
@@ -51,6 +76,99 @@ impl HeaderSections {
     }
 }
 
+/// Inconsistent lengths found by the strict validation
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum LengthError {
+    #[error("total length is {total_length} octets, but the sections add up to {sum}")]
+    TotalLength { total_length: u32, sum: u64 },
+    #[error("section {section} has {length} octets, fewer than the minimum {minimum}")]
+    SectionTooShort {
+        section: u8,
+        length: u32,
+        minimum: u32,
+    },
+    #[error("section {section} has an odd length {length} in edition {edition}")]
+    OddSectionLength {
+        section: u8,
+        length: u32,
+        edition: u8,
+    },
+    #[error("{bits_read} bits read beyond the data section of {section_length} octets")]
+    DataSectionOverrun { section_length: u32, bits_read: u64 },
+    #[error("only {bits_read} bits of the data section of {section_length} octets were read")]
+    DataSectionUnderrun { section_length: u32, bits_read: u64 },
+}
+
+impl HeaderSections {
+    /// Check the section lengths against each other and against the total length, given the
+    /// length of the data section (see `DataReader::section_length`)
+    pub fn validate_lengths(&self, data_section_length: u32) -> Result<(), LengthError> {
+        let edition = self.indicator_section.edition_number;
+        if data_section_length < 4 {
+            return Err(LengthError::SectionTooShort {
+                section: 4,
+                length: data_section_length,
+                minimum: 4,
+            });
+        }
+        let lengths = [
+            (1, self.identification_section.section_length),
+            (
+                2,
+                self.optional_section
+                    .as_ref()
+                    .map_or(0, |s| s.section_length),
+            ),
+            (3, self.data_description_section.section_length),
+            (4, data_section_length),
+        ];
+        if edition <= 3 {
+            // Sections have an even number of octets up to edition 3
+            if let Some(&(section, length)) = lengths.iter().find(|(_, length)| length % 2 != 0) {
+                return Err(LengthError::OddSectionLength {
+                    section,
+                    length,
+                    edition,
+                });
+            }
+        }
+        // Editions 0 and 1 do not have the total length
+        if edition >= 2 {
+            let sum = 8 + lengths.iter().map(|&(_, l)| l as u64).sum::<u64>() + 4;
+            if sum != self.indicator_section.total_length as u64 {
+                return Err(LengthError::TotalLength {
+                    total_length: self.indicator_section.total_length,
+                    sum,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the length of the section at the current position before reading it
+fn check_section_length<R: BinReaderExt>(
+    reader: &mut R,
+    section: u8,
+    minimum: u32,
+) -> Result<(), Error> {
+    let length = three_bytes_to_u32(reader.read_be()?);
+    reader.seek(SeekFrom::Current(-3))?;
+    ensure_section_length(section, length, minimum)
+}
+
+fn ensure_section_length(section: u8, length: u32, minimum: u32) -> Result<(), Error> {
+    if length < minimum {
+        return Err(LengthError::SectionTooShort {
+            section,
+            length,
+            minimum,
+        }
+        .into());
+    }
+    Ok(())
+}
+
 fn three_bytes_to_u32(bytes: (u8, u8, u8)) -> u32 {
     (bytes.0 as u32) << 16 | (bytes.1 as u32) << 8 | (bytes.2 as u32)
 }
@@ -60,6 +178,7 @@ fn three_bytes_to_u32(bytes: (u8, u8, u8)) -> u32 {
 #[cfg_attr(feature = "serde", derive(Serialize))]
 #[brw(magic = b"BUFR")]
 pub struct IndicatorSection {
+    /// Total length of the message (0 for editions 0 and 1, which do not have it)
     #[br(map = three_bytes_to_u32)]
     pub total_length: u32,
     pub edition_number: u8,
@@ -69,6 +188,7 @@ pub struct IndicatorSection {
 #[derive(BinRead, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct IdentificationSection {
+    #[br(assert(section_length >= 22, "Identification section (BUFR4) length must be >= 22"))]
     #[br(map = three_bytes_to_u32)]
     pub section_length: u32,
     pub master_table_number: u8,
@@ -87,13 +207,14 @@ pub struct IdentificationSection {
     pub typical_hour: u8,
     pub typical_minute: u8,
     pub typical_second: u8,
-    #[br(assert(section_length >= 22, "Identification section (BUFR4) length must be >= 22"))]
     #[br(count = section_length - 22)]
     pub local_use: Vec<u8>,
 }
 
-#[derive(BinRead, Debug )]
+/// Identification section (Section 1) of BUFR edition 3
+#[derive(BinRead, Debug)]
 pub struct IdentificationSectionV3 {
+    #[br(assert(section_length >= 17, "Identification section (BUFR3) length must be >= 17"))]
     #[br(map = three_bytes_to_u32)]
     pub section_length: u32,
     pub master_table_number: u8,
@@ -110,7 +231,6 @@ pub struct IdentificationSectionV3 {
     pub typical_day: u8,
     pub typical_hour: u8,
     pub typical_minute: u8,
-    #[br(assert(section_length >= 17, "Identification section (BUFR3) length must be >= 17"))]
     #[br(count = section_length - 17)]
     pub local_use: Vec<u8>,
 }
@@ -140,9 +260,106 @@ impl From<IdentificationSectionV3> for IdentificationSection {
     }
 }
 
+/// Identification section (Section 1) of BUFR edition 2
+#[derive(BinRead, Debug)]
+pub struct IdentificationSectionV2 {
+    #[br(assert(section_length >= 17, "Identification section (BUFR2) length must be >= 17"))]
+    #[br(map = three_bytes_to_u32)]
+    pub section_length: u32,
+    pub master_table_number: u8,
+    pub centre: u16,
+    pub update_sequence_number: u8,
+    pub flags: IdentificationSectionFlags,
+    pub data_category: u8,
+    pub data_sub_category: u8,
+    pub master_table_version: u8,
+    pub local_tables_version: u8,
+    pub typical_year: u8,
+    pub typical_month: u8,
+    pub typical_day: u8,
+    pub typical_hour: u8,
+    pub typical_minute: u8,
+    #[br(count = section_length - 17)]
+    pub local_use: Vec<u8>,
+}
+
+impl From<IdentificationSectionV2> for IdentificationSection {
+    fn from(value: IdentificationSectionV2) -> Self {
+        Self {
+            section_length: value.section_length,
+            master_table_number: value.master_table_number,
+            centre: value.centre,
+            sub_centre: 0,
+            update_sequence_number: value.update_sequence_number,
+            flags: value.flags,
+            data_category: value.data_category,
+            international_data_sub_category: value.data_sub_category,
+            local_data_sub_category: 0,
+            master_table_version: value.master_table_version,
+            local_tables_version: value.local_tables_version,
+            typical_year: value.typical_year as u16,
+            typical_month: value.typical_month,
+            typical_day: value.typical_day,
+            typical_hour: value.typical_hour,
+            typical_minute: value.typical_minute,
+            typical_second: 0,
+            local_use: value.local_use,
+        }
+    }
+}
+
+/// Identification section (Section 1) of BUFR editions 0 and 1, following the four octets
+/// (section length and edition number) already read as the indicator section
+#[derive(BinRead, Debug)]
+#[br(import(section_length: u32))]
+pub struct IdentificationSectionV1 {
+    #[br(assert(section_length >= 17, "Identification section (BUFR1) length must be >= 17"))]
+    #[br(calc = section_length)]
+    pub section_length: u32,
+    pub centre: u16,
+    pub update_sequence_number: u8,
+    pub flags: IdentificationSectionFlags,
+    pub data_category: u8,
+    pub data_sub_category: u8,
+    pub master_table_version: u8,
+    pub local_tables_version: u8,
+    pub typical_year: u8,
+    pub typical_month: u8,
+    pub typical_day: u8,
+    pub typical_hour: u8,
+    pub typical_minute: u8,
+    #[br(count = section_length - 17)]
+    pub local_use: Vec<u8>,
+}
+
+impl From<IdentificationSectionV1> for IdentificationSection {
+    fn from(value: IdentificationSectionV1) -> Self {
+        Self {
+            section_length: value.section_length,
+            master_table_number: 0,
+            centre: value.centre,
+            sub_centre: 0,
+            update_sequence_number: value.update_sequence_number,
+            flags: value.flags,
+            data_category: value.data_category,
+            international_data_sub_category: value.data_sub_category,
+            local_data_sub_category: 0,
+            master_table_version: value.master_table_version,
+            local_tables_version: value.local_tables_version,
+            typical_year: value.typical_year as u16,
+            typical_month: value.typical_month,
+            typical_day: value.typical_day,
+            typical_hour: value.typical_hour,
+            typical_minute: value.typical_minute,
+            typical_second: 0,
+            local_use: value.local_use,
+        }
+    }
+}
+
 #[derive(BinRead, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
-#[br(map = |b: u8| 
+#[br(map = |b: u8|
     Self {
         has_optional_section: b & 0b10000000 != 0,
     }
@@ -155,9 +372,9 @@ pub struct IdentificationSectionFlags {
 #[derive(BinRead, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct OptionalSection {
+    #[br(assert(section_length >= 4, "Optional section length must be >= 4"))]
     #[br(map = three_bytes_to_u32, pad_after = 1)]
     pub section_length: u32,
-    #[br(assert(section_length >= 4, "Optional section length must be >= 4"))]
     #[br(count = section_length - 4)]
     pub optional: Vec<u8>,
 }
@@ -166,11 +383,11 @@ pub struct OptionalSection {
 #[derive(BinRead, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct DataDescriptionSection {
+    #[br(assert(section_length >= 7, "Data description section length must be >= 7"))]
     #[br(map = three_bytes_to_u32, pad_after = 1)]
     pub section_length: u32,
     pub number_of_subsets: u16,
     pub flags: DataDescriptionSectionFlags,
-    #[br(assert(section_length >= 7, "Data description section length must be >= 7"))]
     #[br(count = (section_length - 7) / 2)]
     pub descriptors: Vec<Descriptor>,
     #[br(count = section_length as usize - 7 - (2 * descriptors.len()))]
@@ -197,7 +414,8 @@ pub struct EndSection {}
 
 /// Check if the end section appears in the stream
 pub fn ensure_end_section<R: std::io::Read>(edition: u8, reader: &mut R) -> Result<(), Error> {
-    if edition == 3 {
+    // Sections have an even length up to edition 3, so a padding octet may precede the end
+    if edition <= 3 {
         let mut buf: [u8; 1] = [0; 1];
         reader.read_exact(&mut buf)?;
         match buf[0] {
@@ -208,6 +426,7 @@ pub fn ensure_end_section<R: std::io::Read>(edition: u8, reader: &mut R) -> Resu
                 if &buf != b"777" {
                     return Err(Error::Fatal("Invalid end section".to_string()));
                 }
+                return Ok(());
             }
             _ => {
                 return Err(Error::Fatal("Invalid end section".to_string()));
@@ -221,3 +440,179 @@ pub fn ensure_end_section<R: std::io::Read>(edition: u8, reader: &mut R) -> Resu
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{DataEvent, DataReader, DataSpec, Tables, Value};
+
+    /// Section 1 from octet 5 in editions 0 to 2 (centre 34, 1999-12-31 23:59)
+    const IDENTIFICATION: [u8; 14] = [0, 34, 0, 0, 0, 1, 2, 0, 99, 12, 31, 23, 59, 0];
+
+    /// Sections 3 to 5 with a subset of WMO block number (001001) 44
+    fn make_data_sections() -> Vec<u8> {
+        let mut data = vec![0, 0, 10, 0, 0, 1, 0x80, 0x01, 0x01, 0];
+        data.extend([0, 0, 6, 0, 44 << 1, 0]);
+        data.extend(b"7777");
+        data
+    }
+
+    fn make_message(edition: u8, optional: bool) -> Vec<u8> {
+        let mut identification = IDENTIFICATION;
+        if optional {
+            identification[3] = 0x80;
+        }
+        let mut message = b"BUFR".to_vec();
+        match edition {
+            0 | 1 => message.extend([0, 0, 18, edition]),
+            _ => {
+                message.extend([0, 0, 0, edition]);
+                message.extend([0, 0, 18, 0]);
+            }
+        }
+        if edition == 4 {
+            // Centre, sub-centre, local sub-category, 4-digit year and second
+            message[10] = 22;
+            message.extend([0, 34, 0, 0, 0, identification[3], 0, 1, 0, 2, 0]);
+            message.extend([0x07, 0xcf, 12, 31, 23, 59, 0]);
+        } else {
+            message.extend(identification);
+        }
+        if optional {
+            message.extend([0, 0, 6, 0, 0xab, 0xcd]);
+        }
+        message.extend(make_data_sections());
+        if edition >= 2 {
+            let total_length = (message.len() as u32).to_be_bytes();
+            message[4..7].copy_from_slice(&total_length[1..]);
+        }
+        message
+    }
+
+    #[test]
+    fn test_old_editions() {
+        for edition in [0, 1, 2] {
+            for optional in [false, true] {
+                let message = make_message(edition, optional);
+                let mut reader = Cursor::new(&message);
+                let header = HeaderSections::read(&mut reader).unwrap();
+                assert_eq!(header.indicator_section.edition_number, edition);
+                let expected_length = if edition >= 2 { message.len() } else { 0 };
+                assert_eq!(
+                    header.indicator_section.total_length as usize,
+                    expected_length
+                );
+
+                let section = &header.identification_section;
+                assert_eq!(section.section_length, 18);
+                assert_eq!(section.master_table_number, 0);
+                assert_eq!((section.centre, section.sub_centre), (34, 0));
+                assert_eq!(section.international_data_sub_category, 1);
+                assert_eq!(section.master_table_version, 2);
+                assert_eq!(section.local_use, [0]);
+                assert_eq!(
+                    (
+                        section.typical_year,
+                        section.typical_month,
+                        section.typical_day,
+                        section.typical_hour,
+                        section.typical_minute
+                    ),
+                    (99, 12, 31, 23, 59)
+                );
+                assert_eq!(section.flags.has_optional_section, optional);
+                assert_eq!(
+                    header.optional_section.map(|s| s.optional),
+                    optional.then(|| vec![0xab, 0xcd])
+                );
+                assert_eq!(header.data_description_section.number_of_subsets, 1);
+
+                // The rest of the message is read as in later editions
+                let tables = Tables::default();
+                let data_spec =
+                    DataSpec::from_data_description(&header.data_description_section, &tables)
+                        .unwrap();
+                let mut data_reader = DataReader::new(&mut reader, &data_spec).unwrap();
+                let mut values = vec![];
+                loop {
+                    match data_reader.read_event().unwrap() {
+                        DataEvent::Data { value, .. } => values.push(value),
+                        DataEvent::Eof => break,
+                        _ => {}
+                    }
+                }
+                drop(data_reader);
+                assert!(matches!(values[..], [Value::Integer(44)]));
+                ensure_end_section(edition, &mut reader).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_lengths() {
+        let read = |message: &[u8]| HeaderSections::read(Cursor::new(message)).unwrap();
+        for edition in [0, 1, 2, 4] {
+            let header = read(&make_message(edition, true));
+            assert_eq!(header.validate_lengths(6), Ok(()));
+            assert_eq!(
+                header.validate_lengths(3),
+                Err(LengthError::SectionTooShort {
+                    section: 4,
+                    length: 3,
+                    minimum: 4
+                })
+            );
+        }
+
+        // The sum of the sections is 46 octets
+        let mut message = make_message(2, false);
+        message[6] = 48;
+        assert_eq!(
+            read(&message).validate_lengths(6),
+            Err(LengthError::TotalLength {
+                total_length: 48,
+                sum: 46
+            })
+        );
+
+        // Odd lengths are only allowed from edition 4
+        let header = read(&make_message(2, false));
+        assert_eq!(
+            header.validate_lengths(7),
+            Err(LengthError::OddSectionLength {
+                section: 4,
+                length: 7,
+                edition: 2
+            })
+        );
+        let mut message = make_message(4, false);
+        message[6] += 1;
+        assert_eq!(read(&message).validate_lengths(7), Ok(()));
+
+        // Too short sections are errors while reading
+        let too_short = |edition, optional, pos, length| {
+            let mut message = make_message(edition, optional);
+            message[pos] = length;
+            match HeaderSections::read(Cursor::new(&message)) {
+                Err(Error::Length(LengthError::SectionTooShort { section, .. })) => Some(section),
+                _ => None,
+            }
+        };
+        assert_eq!(too_short(4, false, 10, 20), Some(1));
+        assert_eq!(too_short(1, false, 6, 16), Some(1));
+        assert_eq!(too_short(2, true, 28, 3), Some(2));
+        assert_eq!(too_short(2, false, 28, 6), Some(3));
+    }
+
+    #[test]
+    fn test_unsupported_edition() {
+        let mut message = make_message(4, false);
+        message[7] = 5;
+        assert!(matches!(
+            HeaderSections::read(Cursor::new(&message)),
+            Err(Error::Fatal(_))
+        ));
+    }
+}
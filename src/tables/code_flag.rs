@@ -0,0 +1,7 @@
+//! This file is generated from BUFRCREX_CodeFlag_en.txt by tables_to_rs.py.
+//!
+//! It is empty until the tables are generated from the WMO BUFR4 repository.
+
+use super::CodeFlagEntry;
+
+pub static CODE_FLAG: [CodeFlagEntry; 0] = [];
@@ -1,12 +1,16 @@
+mod code_flag;
 pub mod local;
 mod table_b;
 mod table_c;
 mod table_d;
 
+pub use code_flag::*;
 pub use table_b::*;
 pub use table_c::*;
 pub use table_d::*;
 
+use std::ops::RangeInclusive;
+
 use crate::{Descriptor, XY};
 use hashbrown::HashMap;
 
@@ -14,6 +18,7 @@ pub struct Tables {
     pub table_b: HashMap<XY, &'static TableBEntry>,
     pub table_c: HashMap<(u8, Option<u8>), &'static TableCEntry>,
     pub table_d: HashMap<XY, &'static TableDEntry>,
+    pub code_flag: HashMap<XY, Vec<&'static CodeFlagEntry>>,
 }
 
 impl Default for Tables {
@@ -22,10 +27,63 @@ impl Default for Tables {
             table_b: make_table_b(),
             table_c: make_table_c(),
             table_d: make_table_d(),
+            code_flag: make_code_flag(),
         }
     }
 }
 
+impl Tables {
+    /// Add code and flag table entries, such as those of a local table
+    pub fn extend_code_flag(&mut self, entries: &'static [CodeFlagEntry]) {
+        for entry in entries {
+            self.code_flag.entry(entry.xy).or_default().push(entry);
+        }
+    }
+
+    /// Meaning of a code table value of the element `xy`
+    ///
+    /// Returns `None` if the value is missing or not found in the table.
+    pub fn code_meaning(&self, xy: XY, value: u64) -> Option<&'static str> {
+        if self.is_missing(xy, value)? {
+            return None;
+        }
+        let value = u32::try_from(value).ok()?;
+        self.code_flag
+            .get(&xy)?
+            .iter()
+            .find(|entry| entry.codes.contains(&value))
+            .map(|entry| entry.meaning)
+    }
+
+    /// Meanings of the bits set in a flag table value of the element `xy`
+    ///
+    /// Returns `None` if the value is missing or the element has no flag table.
+    pub fn flag_meanings(&self, xy: XY, value: u64) -> Option<Vec<&'static str>> {
+        if self.is_missing(xy, value)? {
+            return None;
+        }
+        let bits = self.table_b.get(&xy)?.bits as u32;
+        let entries = self.code_flag.get(&xy)?;
+        // Bit No. 1 is the most significant bit
+        let is_set =
+            |bit_no: u32| (1..=bits).contains(&bit_no) && value >> (bits - bit_no) & 1 == 1;
+        Some(
+            entries
+                .iter()
+                .filter(|entry| entry.codes.clone().any(is_set))
+                .map(|entry| entry.meaning)
+                .collect(),
+        )
+    }
+
+    /// Whether the raw value of the element `xy` is missing (`None` for unknown elements)
+    fn is_missing(&self, xy: XY, value: u64) -> Option<bool> {
+        let entry = self.table_b.get(&xy)?;
+        let bits = entry.bits as u32;
+        Some(entry.has_missing_value(bits) && value == u64::MAX >> 64u32.saturating_sub(bits))
+    }
+}
+
 #[derive(Debug)]
 pub struct TableBEntry {
     pub xy: XY,
@@ -37,6 +95,26 @@ pub struct TableBEntry {
     pub bits: u16,
 }
 
+impl TableBEntry {
+    /// Whether the element is character data (CCITT IA5)
+    pub fn is_character(&self) -> bool {
+        self.unit == "CCITT IA5"
+    }
+
+    /// Whether the element is a code table or a flag table
+    pub fn is_code_or_flag_table(&self) -> bool {
+        self.unit.contains("Code table") || self.unit == "Flag table"
+    }
+
+    /// Whether all bits set to 1 means a missing value when encoded in `bit_width` bits
+    ///
+    /// Elements of class 31 (e.g. delayed replication factors) and 1-bit elements are never
+    /// missing. Flag tables are missing only when all bits are set.
+    pub fn has_missing_value(&self, bit_width: u32) -> bool {
+        self.xy.x != 31 && bit_width > 1
+    }
+}
+
 #[derive(Debug)]
 pub struct TableCEntry {
     pub xy: (u8, Option<u8>),
@@ -44,6 +122,15 @@ pub struct TableCEntry {
     pub operation_definition: &'static str,
 }
 
+/// An entry of a code table or a flag table
+#[derive(Debug)]
+pub struct CodeFlagEntry {
+    pub xy: XY,
+    /// Code figures, or bit numbers of a flag table (Bit No. 1 is the most significant bit)
+    pub codes: RangeInclusive<u32>,
+    pub meaning: &'static str,
+}
+
 #[derive(Debug)]
 pub struct TableDEntry {
     pub xy: XY,
@@ -71,6 +158,15 @@ fn make_table_c() -> HashMap<(u8, Option<u8>), &'static TableCEntry> {
     map
 }
 
+/// Code and flag tables
+fn make_code_flag() -> HashMap<XY, Vec<&'static CodeFlagEntry>> {
+    let mut map: HashMap<XY, Vec<_>> = HashMap::new();
+    for entry in &code_flag::CODE_FLAG {
+        map.entry(entry.xy).or_default().push(entry);
+    }
+    map
+}
+
 /// Table D (f = 3)
 fn make_table_d() -> HashMap<XY, &'static TableDEntry> {
     let mut map = HashMap::new();
@@ -79,3 +175,81 @@ fn make_table_d() -> HashMap<XY, &'static TableDEntry> {
     }
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LOCAL_CODE_FLAG: [CodeFlagEntry; 6] = [
+        CodeFlagEntry {
+            xy: XY { x: 2, y: 1 },
+            codes: 0..=0,
+            meaning: "Automatic station",
+        },
+        CodeFlagEntry {
+            xy: XY { x: 2, y: 1 },
+            codes: 1..=1,
+            meaning: "Manned station",
+        },
+        CodeFlagEntry {
+            xy: XY { x: 2, y: 1 },
+            codes: 2..=2,
+            meaning: "Hybrid: both manned and automatic",
+        },
+        CodeFlagEntry {
+            xy: XY { x: 8, y: 42 },
+            codes: 1..=1,
+            meaning: "Surface",
+        },
+        CodeFlagEntry {
+            xy: XY { x: 8, y: 42 },
+            codes: 2..=2,
+            meaning: "Standard level",
+        },
+        CodeFlagEntry {
+            xy: XY { x: 8, y: 42 },
+            codes: 16..=17,
+            meaning: "Reserved",
+        },
+    ];
+
+    static LOCAL_DATA_PRESENT: [CodeFlagEntry; 1] = [CodeFlagEntry {
+        xy: XY { x: 31, y: 31 },
+        codes: 1..=1,
+        meaning: "Data not present",
+    }];
+
+    #[test]
+    fn test_code_flag() {
+        let mut tables = Tables::default();
+        tables.code_flag.clear();
+        tables.extend_code_flag(&LOCAL_CODE_FLAG);
+
+        // 0-02-001 is a 2-bit code table
+        let station_type = XY { x: 2, y: 1 };
+        assert_eq!(tables.code_meaning(station_type, 1), Some("Manned station"));
+        assert_eq!(tables.code_meaning(station_type, 3), None);
+
+        // 0-08-042 is an 18-bit flag table
+        let significance = XY { x: 8, y: 42 };
+        assert_eq!(
+            tables.flag_meanings(significance, 1 << 17),
+            Some(vec!["Surface"])
+        );
+        assert_eq!(
+            tables.flag_meanings(significance, 0b11 << 16 | 0b10),
+            Some(vec!["Surface", "Standard level", "Reserved"])
+        );
+        assert_eq!(tables.flag_meanings(significance, 0), Some(vec![]));
+        assert_eq!(tables.flag_meanings(significance, (1 << 18) - 1), None);
+        assert_eq!(tables.flag_meanings(XY { x: 99, y: 99 }, 0), None);
+
+        // All bits set is not missing for class 31 and 1-bit elements
+        tables.extend_code_flag(&LOCAL_DATA_PRESENT);
+        let data_present = XY { x: 31, y: 31 };
+        assert_eq!(
+            tables.flag_meanings(data_present, 1),
+            Some(vec!["Data not present"])
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Parsing and conversion of Table B units (e.g. "m s-1", "kg m-2", "K")
+//!
+//! Not supported are calendar units without a fixed length ("a" and "mon"), logarithmic units
+//! ("dB", "log (m-3)"), fractional exponents ("m2/3 s-1"), counts of other units ("DU", "NTU",
+//! "pH unit", "N units"), and code tables, flag tables and characters.
+
+use std::f64::consts::PI;
+
+use crate::Error;
+
+/// Exponents of the base dimensions: m, kg, s, K, A, mol, cd and rad
+type Dimension = [i8; 8];
+
+const M: Dimension = [1, 0, 0, 0, 0, 0, 0, 0];
+const KG: Dimension = [0, 1, 0, 0, 0, 0, 0, 0];
+const S: Dimension = [0, 0, 1, 0, 0, 0, 0, 0];
+const K: Dimension = [0, 0, 0, 1, 0, 0, 0, 0];
+const A: Dimension = [0, 0, 0, 0, 1, 0, 0, 0];
+const MOL: Dimension = [0, 0, 0, 0, 0, 1, 0, 0];
+const CD: Dimension = [0, 0, 0, 0, 0, 0, 1, 0];
+const RAD: Dimension = [0, 0, 0, 0, 0, 0, 0, 1];
+const ONE: Dimension = [0; 8];
+
+/// A physical unit as a linear mapping to the base units:
+/// `base = value * factor + offset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+impl Unit {
+    const fn new(dimension: Dimension, factor: f64) -> Self {
+        Self {
+            dimension,
+            factor,
+            offset: 0.0,
+        }
+    }
+
+    /// Parse a unit such as "K", "m s-1", "m/s", "kg m-2", "hPa" or "kt"
+    pub fn parse(unit: &str) -> Result<Self, Error> {
+        let unit = unit.trim();
+        match unit {
+            "Numeric" | "%" | "0/00" | "C" | "degC" | "F" | "degF" | "degree true" => {
+                return symbol(unit).ok_or_else(|| unknown(unit));
+            }
+            _ => {}
+        }
+        let mut result = Unit::new(ONE, 1.0);
+        for term in unit.split_whitespace() {
+            for (i, factor) in term.split('/').enumerate() {
+                if i > 0 && factor.is_empty() {
+                    return Err(unknown(unit));
+                }
+                if factor.is_empty() {
+                    // e.g. "/s"
+                    continue;
+                }
+                let pos = factor
+                    .find(|c: char| c.is_ascii_digit() || c == '-')
+                    .unwrap_or(factor.len());
+                let (name, exponent) = factor.split_at(pos);
+                let exponent: i8 = match exponent {
+                    "" => 1,
+                    e => e.parse().map_err(|_| unknown(unit))?,
+                };
+                let exponent = if i > 0 {
+                    exponent.checked_neg().ok_or_else(|| unknown(unit))?
+                } else {
+                    exponent
+                };
+                let Some(base) = symbol(name).filter(|b| b.offset == 0.0) else {
+                    return Err(unknown(unit));
+                };
+                result = base
+                    .pow(exponent)
+                    .and_then(|power| result.mul(&power))
+                    .ok_or_else(|| unknown(unit))?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Whether values can be converted between the two units
+    pub fn is_compatible(&self, other: &Unit) -> bool {
+        self.dimension == other.dimension
+    }
+
+    /// Convert a value in this unit into `target`
+    pub fn convert(&self, value: f64, target: &Unit) -> Result<f64, Error> {
+        if !self.is_compatible(target) {
+            return Err(Error::UnitConversion(format!(
+                "Incompatible units {:?} and {:?}",
+                self.dimension, target.dimension
+            )));
+        }
+        Ok((value * self.factor + self.offset - target.offset) / target.factor)
+    }
+
+    /// Product of two units, or None if an exponent overflows
+    fn mul(&self, other: &Unit) -> Option<Unit> {
+        let mut dimension = self.dimension;
+        for (d, o) in dimension.iter_mut().zip(other.dimension) {
+            *d = d.checked_add(o)?;
+        }
+        Some(Unit::new(dimension, self.factor * other.factor))
+    }
+
+    /// Power of a unit, or None if an exponent overflows
+    fn pow(&self, exponent: i8) -> Option<Unit> {
+        let mut dimension = self.dimension;
+        for d in dimension.iter_mut() {
+            *d = d.checked_mul(exponent)?;
+        }
+        Some(Unit::new(dimension, self.factor.powi(exponent as i32)))
+    }
+}
+
+/// Convert a value between two units given as strings
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64, Error> {
+    let (source, target) = (Unit::parse(from)?, Unit::parse(to)?);
+    source
+        .convert(value, &target)
+        .map_err(|_| Error::UnitConversion(format!("Cannot convert from {:?} to {:?}", from, to)))
+}
+
+fn unknown(unit: &str) -> Error {
+    Error::UnitConversion(format!("Unknown unit {:?}", unit))
+}
+
+fn symbol(name: &str) -> Option<Unit> {
+    const fn derived(exponents: &[(Dimension, i8)], factor: f64) -> Unit {
+        let mut dimension = ONE;
+        let mut i = 0;
+        while i < exponents.len() {
+            let (base, e) = exponents[i];
+            let mut j = 0;
+            while j < dimension.len() {
+                dimension[j] += base[j] * e;
+                j += 1;
+            }
+            i += 1;
+        }
+        Unit::new(dimension, factor)
+    }
+    // kg m2 s-2
+    const JOULE: Unit = derived(&[(KG, 1), (M, 2), (S, -2)], 1.0);
+    // kg m-1 s-2
+    const PASCAL: Unit = derived(&[(KG, 1), (M, -1), (S, -2)], 1.0);
+    // kg m2 s-3
+    const WATT: Unit = derived(&[(KG, 1), (M, 2), (S, -3)], 1.0);
+    // m2 s-2 (J/kg)
+    const SIEVERT: Unit = derived(&[(M, 2), (S, -2)], 1.0);
+    // A-1 kg m2 s-3
+    const VOLT: Unit = derived(&[(KG, 1), (M, 2), (S, -3), (A, -1)], 1.0);
+
+    Some(match name {
+        "Numeric" => Unit::new(ONE, 1.0),
+        "%" => Unit::new(ONE, 0.01),
+        "0/00" => Unit::new(ONE, 0.001),
+        // Length
+        "m" | "gpm" => Unit::new(M, 1.0),
+        "km" => Unit::new(M, 1000.0),
+        "cm" => Unit::new(M, 0.01),
+        "mm" => Unit::new(M, 0.001),
+        "um" => Unit::new(M, 1e-6),
+        "ft" => Unit::new(M, 0.3048),
+        "l" => derived(&[(M, 3)], 1e-3),
+        // Mass
+        "kg" => Unit::new(KG, 1.0),
+        "g" => Unit::new(KG, 1e-3),
+        "mg" => Unit::new(KG, 1e-6),
+        // Time
+        "s" => Unit::new(S, 1.0),
+        "min" => Unit::new(S, 60.0),
+        "h" => Unit::new(S, 3600.0),
+        "d" => Unit::new(S, 86400.0),
+        "Hz" | "Bq" => derived(&[(S, -1)], 1.0),
+        // Temperature
+        "K" => Unit::new(K, 1.0),
+        "C" | "degC" => Unit {
+            offset: 273.15,
+            ..Unit::new(K, 1.0)
+        },
+        "F" | "degF" => Unit {
+            offset: 459.67 * 5.0 / 9.0,
+            ..Unit::new(K, 5.0 / 9.0)
+        },
+        // Angles
+        "rad" => Unit::new(RAD, 1.0),
+        "deg" | "degree true" => Unit::new(RAD, PI / 180.0),
+        "sr" => derived(&[(RAD, 2)], 1.0),
+        // Speed
+        "kt" | "knot" => derived(&[(M, 1), (S, -1)], 1852.0 / 3600.0),
+        // Derived units
+        "Pa" => PASCAL,
+        "hPa" => Unit::new(PASCAL.dimension, 100.0),
+        "kPa" => Unit::new(PASCAL.dimension, 1000.0),
+        "J" => JOULE,
+        "W" => WATT,
+        "N" => derived(&[(KG, 1), (M, 1), (S, -2)], 1.0),
+        "A" => Unit::new(A, 1.0),
+        "V" => VOLT,
+        "S" => derived(&[(KG, -1), (M, -2), (S, 3), (A, 2)], 1.0),
+        "Sv" => SIEVERT,
+        "mSv" => Unit::new(SIEVERT.dimension, 1e-3),
+        "nSv" => Unit::new(SIEVERT.dimension, 1e-9),
+        "mol" => Unit::new(MOL, 1.0),
+        "umol" => Unit::new(MOL, 1e-6),
+        "cd" | "Cd" => Unit::new(CD, 1.0),
+        "lm" => derived(&[(CD, 1), (RAD, 2)], 1.0),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_close(convert(273.15, "K", "C").unwrap(), 0.0);
+        assert_close(convert(300.0, "K", "degC").unwrap(), 26.85);
+        assert_close(convert(-40.0, "C", "F").unwrap(), -40.0);
+        assert_close(convert(101325.0, "Pa", "hPa").unwrap(), 1013.25);
+        assert_close(convert(10.0, "m/s", "kt").unwrap(), 19.438444924406047);
+        assert_close(convert(10.0, "m s-1", "km/h").unwrap(), 36.0);
+        assert_close(convert(12.5, "kg m-2 s-1", "kg m-2 h-1").unwrap(), 45000.0);
+        assert_close(convert(180.0, "degree true", "rad").unwrap(), PI);
+        assert_close(convert(50.0, "%", "Numeric").unwrap(), 0.5);
+        assert_close(convert(1.0, "J/kg", "mSv").unwrap(), 1000.0);
+        assert_close(convert(1.0, "W", "J s-1").unwrap(), 1.0);
+        assert_close(convert(2.0, "S/m", "A V-1 m-1").unwrap(), 2.0);
+        assert_close(convert(3.0, "Cd m-2", "cd m-2").unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_reject() {
+        // Incompatible dimensions
+        assert!(matches!(
+            convert(1.0, "K", "Pa"),
+            Err(Error::UnitConversion(_))
+        ));
+        assert!(convert(1.0, "deg", "%").is_err());
+        assert!(convert(1.0, "kg m-2", "mm").is_err());
+        // Unknown or unsupported units
+        assert!(Unit::parse("Code table").is_err());
+        assert!(Unit::parse("dB").is_err());
+        assert!(Unit::parse("m2/3 s-1").is_err());
+        assert!(Unit::parse("log (m-3)").is_err());
+        assert!(Unit::parse("a").is_err());
+        assert!(Unit::parse("mon").is_err());
+        // Units with an offset cannot be combined
+        assert!(Unit::parse("C m-1").is_err());
+        // Exponents overflowing an i8
+        assert!(matches!(
+            Unit::parse("m127 m127"),
+            Err(Error::UnitConversion(_))
+        ));
+        assert!(matches!(
+            Unit::parse("/m-128"),
+            Err(Error::UnitConversion(_))
+        ));
+        assert!(matches!(
+            Unit::parse("J-100"),
+            Err(Error::UnitConversion(_))
+        ));
+    }
+}
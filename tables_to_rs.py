@@ -27,6 +27,12 @@ TABLE_D_HEAD = """
 use super::{Descriptor, XY, TableDEntry};
 """
 
+CODE_FLAG_HEAD = """
+//! This file is generated from BUFRCREX_CodeFlag_en.txt by tables_to_rs.py.
+
+use super::{XY, CodeFlagEntry};
+"""
+
 
 def escape(s: str | float) -> str:
     if isinstance(s, str):
@@ -139,11 +145,48 @@ def make_table_d() -> None:
             f.write("];\n")
 
 
+def make_code_flag() -> None:
+    df = pd.read_csv("./BUFR4/txt/BUFRCREX_CodeFlag_en.txt", dtype=str)
+    entries = []
+    for _, row in df.iterrows():
+        if row["Status"] == "Deprecated":  # type: ignore
+            continue
+        fxy = int(row["FXY"])  # type: ignore
+        x = (fxy % 100000) // 1000
+        y = fxy % 1000
+        # Code figures are either a number or a range such as "4-6"
+        code = str(row["CodeFigure"]).replace("\u2013", "-").replace(" ", "")
+        lo, _, hi = code.partition("-")
+        hi = hi or lo
+        if not (lo.isdigit() and hi.isdigit()):
+            # e.g. "All 18" (missing value of flag tables)
+            continue
+        meaning = ""
+        for column in ["EntryName_en", "EntryName_sub1_en", "EntryName_sub2_en"]:
+            if isinstance(row[column], str):
+                meaning = escape(row[column])  # type: ignore
+                break
+        entries.append((x, y, int(lo), int(hi), meaning))
+
+    with open("./src/tables/code_flag.rs", "w") as f:
+        f.write(CODE_FLAG_HEAD)
+        f.write("\n")
+        f.write(f"pub static CODE_FLAG: [CodeFlagEntry; {len(entries)}] = [\n")
+        for x, y, lo, hi, meaning in entries:
+            f.write("CodeFlagEntry {\n")
+            f.write(f"xy: XY {{ x: {x}, y: {y} }},\n")
+            f.write(f"codes: {lo}..={hi},\n")
+            f.write(f'meaning: "{meaning}",\n')
+            f.write("},\n")
+        f.write("];")
+
+
 def main():
     print("generating...")
     make_table_b()
     make_table_c()
     make_table_d()
+    make_code_flag()
     print("cargo fmt...")
     subprocess.run(["cargo", "fmt"], check=True)
 
@@ -1,63 +1,72 @@
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io::Cursor;
 
 use tinybufr::tables::local::jma::{JMA_DATA_DESCRIPTORS, JMA_SEQUENCE_DESCRIPTORS};
 use tinybufr::*;
 
 #[test]
 fn test_amedas() {
-    read_example(
-        "./tests/data/jma/Z__C_RJTD_20210918110000_OBS_AMDS_Rjp_N2_bufr4.bin",
-        false,
-    );
-    read_example(
-        "./tests/data/jma/Z__C_RJTD_20210918110000_OBS_AMDSRR_Rjp_N1_bufr4.bin",
-        false,
-    );
+    read_example("./tests/data/jma/Z__C_RJTD_20210918110000_OBS_AMDS_Rjp_N2_bufr4.bin");
+    read_example("./tests/data/jma/Z__C_RJTD_20210918110000_OBS_AMDSRR_Rjp_N1_bufr4.bin");
 }
 
 #[test]
 fn test_wind_profiler() {
-    read_example(
-        "./tests/data/jma/Z__C_RJTD_20200728040000_WPR_SEQ_RS-all_Pww_bufr4.bin",
-        false,
-    );
+    read_example("./tests/data/jma/Z__C_RJTD_20200728040000_WPR_SEQ_RS-all_Pww_bufr4.bin");
 }
 
 #[test]
 fn test_ryuikishisu() {
     read_example(
         "./tests/data/jma/Z__C_RJTD_20230815070000_MET_SEQ_Ggis1km_Proi_Aper10min_RJsuikei830_ANAL_bufr4.bin",
-        false,
     );
     read_example(
         "./tests/data/jma/Z__C_RJTD_20230815070000_MET_SEQ_Ggis1km_Proi_Fper10min_RJsuikei811_FH0010-0100_bufr4.bin",
-        false,
     );
 }
 
 #[test]
 fn test_istc62() {
-    read_example(
-        "./tests/data/jma/ISTC62_RJTD_310000_201707310002140_001_93839.bin",
-        true,
-    );
+    read_example("./tests/data/jma/ISTC62_RJTD_310000_201707310002140_001_93839.bin");
 }
 
 #[test]
 fn test_istc82() {
-    read_example("./tests/data/jma/ISTC82.dat", true);
+    read_example("./tests/data/jma/ISTC82.dat");
+}
+
+#[test]
+fn test_bulletin_heading() {
+    for (filename, ttaaii, time) in [
+        (
+            "./tests/data/jma/ISTC62_RJTD_310000_201707310002140_001_93839.bin",
+            "ISTC62",
+            (31, 0, 0),
+        ),
+        ("./tests/data/jma/ISTC82.dat", "ISTC82", (28, 23, 50)),
+    ] {
+        let data = fs::read(filename).unwrap();
+        let bulletins = Bulletin::parse_all(&data).unwrap();
+        assert_eq!(bulletins.len(), 1);
+        let bulletin = Bulletin::parse(&data).unwrap();
+        assert_eq!(bulletins[0].heading, bulletin.heading);
+        let heading = &bulletin.heading;
+        assert_eq!(heading.ttaaii, ttaaii);
+        assert_eq!(heading.cccc, "RJTD");
+        assert_eq!((heading.day, heading.hour, heading.minute), time);
+        assert!(!heading.is_correction());
+        assert_eq!(bulletin.sequence_number, None);
+        assert_eq!(bulletin.messages.len(), 1);
+        assert_eq!(bulletin.messages[0].offset, 19);
+    }
 }
 
 #[test]
 fn test_ixac41() {
-    read_example(
-        "./tests/data/jma/201806180758.20230110141530_520.BUFR",
-        false,
-    );
+    read_example("./tests/data/jma/201806180758.20230110141530_520.BUFR");
 }
 
-fn read_example(filename: &str, skip_first_line: bool) {
+fn read_example(filename: &str) {
     // Extend the default tables with JMA local descriptors
     let mut tables = Tables::default();
     for desc in &JMA_DATA_DESCRIPTORS {
@@ -67,22 +76,36 @@ fn read_example(filename: &str, skip_first_line: bool) {
         tables.table_d.insert(seq.xy, seq);
     }
 
+    // Some files have a GTS heading before the BUFR message
     let file = fs::File::open(filename).unwrap();
-    let mut reader = BufReader::new(file);
-    if skip_first_line {
-        // Some files have a first line that is not part of the BUFR message
-        let mut buf = String::new();
-        reader.read_line(&mut buf).unwrap();
-    }
+    let messages: Vec<_> = MessageScanner::new(file).map(Result::unwrap).collect();
+    assert_eq!(messages.len(), 1);
+    let mut reader = Cursor::new(&messages[0].data);
 
     // Parse header sections
     let header = HeaderSections::read(&mut reader).unwrap();
     println!("{}", serde_json::to_string_pretty(&header).unwrap());
 
+    // Cross-check the file name with the identification section. The ISTC* and *.BUFR
+    // fixtures are named after their GTS headings instead of the WMO file naming convention.
+    let basename = filename.rsplit('/').next().unwrap();
+    if basename.starts_with("Z__C_") {
+        let name = WmoFileName::parse(filename).unwrap();
+        assert_eq!(name.originator, "RJTD");
+        name.check_header(&header).unwrap();
+    } else {
+        assert!(basename.starts_with("ISTC") || basename.ends_with(".BUFR"));
+        assert!(WmoFileName::parse(filename).is_err());
+    }
+
     // Parse data section
     let data_spec =
         DataSpec::from_data_description(&header.data_description_section, &tables).unwrap();
     let mut data_reader = DataReader::new(&mut reader, &data_spec).unwrap();
+    data_reader.set_strict(Some(header.indicator_section.edition_number));
+    header
+        .validate_lengths(data_reader.section_length())
+        .unwrap();
 
     let mut subset_counter = 0;
     let mut sequence_counter = 0;